@@ -14,13 +14,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{net::SocketAddr, path::PathBuf};
+use std::{net::{IpAddr, SocketAddr, ToSocketAddrs}, path::PathBuf, sync::Arc};
 
-use axum::{http::{Uri, StatusCode}, BoxError, extract::Host, response::Redirect, Router, handler::HandlerWithoutStateExt};
-use axum_server::{Handle, tls_rustls::RustlsConfig};
-use futures::{StreamExt, FutureExt};
+use anyhow::Context;
+
+use axum::{body::Body, extract::ConnectInfo, http::{header, uri::Authority, Request, Uri, StatusCode}, BoxError, response::{IntoResponse, Redirect}, Router, handler::HandlerWithoutStateExt};
+use axum_server::{accept::DefaultAcceptor, Handle, tls_rustls::{RustlsAcceptor, RustlsConfig}};
+use futures::{stream::FuturesUnordered, StreamExt, FutureExt};
 use signal_hook::consts::{SIGTERM, SIGINT};
 use signal_hook_tokio::Signals;
+use tower::ServiceExt;
+
+mod limit;
+pub use limit::ConnectionLimits;
 
 #[derive(Copy, Clone, serde::Deserialize)]
 struct Ports {
@@ -28,59 +34,160 @@ struct Ports {
     https: u16,
 }
 
+/// One or more addresses to listen on, as IP addresses or host names. For a
+/// dual-stack deployment, list `::` alone. Where IPv6 sockets also accept
+/// IPv4 connections, which is the default on Linux, listening on both
+/// `0.0.0.0` and `::` fails because the port is already in use.
+#[derive(Clone, serde::Deserialize)]
+#[serde(try_from = "AddressList")]
+struct Addresses(Vec<String>);
+
 #[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum AddressList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl TryFrom<AddressList> for Addresses {
+    type Error = &'static str;
+
+    fn try_from(list: AddressList) -> Result<Self, Self::Error> {
+        let addresses = match list {
+            AddressList::One(address) => vec![address],
+            AddressList::Many(addresses) => addresses,
+        };
+        if addresses.is_empty() {
+            return Err("at least one address to listen on is required");
+        }
+        Ok(Addresses(addresses))
+    }
+}
+
+impl Addresses {
+    /// Resolves the addresses, with `port`. A host name is listened on at
+    /// every address it resolves to, e.g. both 127.0.0.1 and ::1 for
+    /// localhost.
+    fn with_port(&self, port: u16) -> anyhow::Result<Vec<SocketAddr>> {
+        let mut resolved = Vec::new();
+        for address in self.0.iter() {
+            let host = address.trim_start_matches('[').trim_end_matches(']');
+            let addrs = (host, port)
+                .to_socket_addrs()
+                .with_context(|| format!("failed to resolve {}", address))?;
+            for addr in addrs {
+                if !resolved.contains(&addr) {
+                    resolved.push(addr);
+                }
+            }
+        }
+        Ok(resolved)
+    }
+}
+
+#[derive(Clone, serde::Deserialize)]
 pub struct Configuration {
-    address: String,
+    #[serde(alias = "addresses")]
+    address: Addresses,
     ports: Ports,
     #[serde(rename = "certificate-file")]
     certificate_file: String,
     #[serde(rename = "key-file")]
     key_file: String,
+    /// Addresses of reverse proxies whose X-Forwarded-Host and
+    /// X-Forwarded-Proto headers are honored. The headers are ignored on
+    /// requests from any other address.
+    #[serde(rename = "trusted-proxies", default)]
+    trusted_proxies: Vec<IpAddr>,
+    #[serde(default)]
+    limits: ConnectionLimits,
+}
+
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+
+fn request_host(request: &Request<Body>, from_proxy: bool) -> Option<String> {
+    let headers = request.headers();
+    headers
+        .get(X_FORWARDED_HOST)
+        .filter(|_| from_proxy)
+        .or_else(|| headers.get(header::HOST))
+        .and_then(|host| host.to_str().ok())
+        .map(|host| host.to_string())
+        .or_else(|| request.uri().authority().map(|authority| authority.to_string()))
 }
 
-async fn redirect_http_to_https(address: String, ports: Ports) {
+/// Redirects plain HTTP requests to the HTTPS listener. Requests that one of
+/// `trusted_proxies`, terminating TLS, marks as HTTPS are served by `app`
+/// directly instead.
+async fn redirect_http_to_https(
+    addr: SocketAddr,
+    ports: Ports,
+    app: Router,
+    trusted_proxies: Arc<Vec<IpAddr>>,
+    acceptor: limit::LimitAcceptor<DefaultAcceptor>,
+) -> std::io::Result<()> {
     fn make_https(host: String, uri: Uri, ports: Ports) -> Result<Uri, BoxError> {
         let mut parts = uri.into_parts();
 
         parts.scheme = Some(axum::http::uri::Scheme::HTTPS);
 
         if parts.path_and_query.is_none() {
-            parts.path_and_query = Some("/".parse().unwrap());
+            parts.path_and_query = Some("/".parse()?);
         }
 
-        let https_host = host.replace(&ports.http.to_string(), &ports.https.to_string());
+        // Authority::host() keeps the brackets around IPv6 literals, so the
+        // port can be appended without corrupting the address.
+        let authority: Authority = host.parse()?;
+        let https_host = authority.host().to_string() + ":" + &ports.https.to_string();
         parts.authority = Some(https_host.parse()?);
 
         Ok(Uri::from_parts(parts)?)
     }
 
-    let redirect = move |Host(host): Host, uri: Uri| async move {
-        match make_https(host, uri, ports) {
-            Ok(uri) => Ok(Redirect::permanent(&uri.to_string())),
+    let redirect = move |ConnectInfo(peer): ConnectInfo<SocketAddr>, request: Request<Body>| async move {
+        // IPv4 clients of an IPv6 listener appear as IPv4-mapped addresses.
+        let from_proxy = trusted_proxies.contains(&peer.ip().to_canonical());
+        if from_proxy {
+            let proto = request.headers().get(X_FORWARDED_PROTO);
+            if proto.is_some_and(|proto| proto == "https") {
+                return app.oneshot(request).await.into_response();
+            }
+        }
+
+        let host = match request_host(&request, from_proxy) {
+            Some(host) => host,
+            // Clients may leave out Host, e.g. with HTTP/1.0. The address of
+            // the listener will do, unless it's a wildcard.
+            None if !addr.ip().is_unspecified() => addr.to_string(),
+            None => return StatusCode::BAD_REQUEST.into_response(),
+        };
+        match make_https(host, request.uri().clone(), ports) {
+            Ok(uri) => Redirect::permanent(&uri.to_string()).into_response(),
             Err(error) => {
                 tracing::warn!(%error, "failed to convert URI to HTTPS");
-                Err(StatusCode::BAD_REQUEST)
+                StatusCode::BAD_REQUEST.into_response()
             }
         }
     };
 
-    let addr: SocketAddr = (address + ":" + &ports.http.to_string()).parse().unwrap();
     tracing::debug!("http redirect listening on {}", addr);
 
-    axum::Server::bind(&addr)
-        .serve(redirect.into_make_service())
+    axum_server::bind(addr)
+        .acceptor(acceptor)
+        .serve(redirect.into_make_service_with_connect_info::<SocketAddr>())
         .await
-        .unwrap();
 }
 
 pub async fn serve(config: Configuration, app: Router) -> anyhow::Result<()> {
     let server_handle = Handle::new();
     let signals = Signals::new(&[SIGINT, SIGTERM])?;
     let signals_handle = signals.handle();
+    let shutdown_handle = server_handle.clone();
     let signal_handler = |mut signals: Signals| async move {
         if let Some(_) = signals.next().await {
             println!("Gracefully shutting down");
-            server_handle.shutdown();
+            shutdown_handle.shutdown();
         }
     };
 
@@ -89,23 +196,59 @@ pub async fn serve(config: Configuration, app: Router) -> anyhow::Result<()> {
         PathBuf::from(config.key_file),
     )
     .await
-    .unwrap();
+    .context("failed to load the certificate and key")?;
 
-    let https_address = config.address.clone() + ":" + &config.ports.https.to_string();
+    // One acceptor for every listener, so that the limits apply to the service
+    // as a whole.
+    let limits = limit::LimitAcceptor::new(DefaultAcceptor::new(), &config.limits);
 
     let signals_task = tokio::spawn(signal_handler(signals)).fuse();
-    let https_server = axum_server::bind_rustls(https_address.parse().unwrap(), tls_config)
-        .serve(app.into_make_service())
-        .fuse();
-    let http_server = tokio::spawn(redirect_http_to_https(config.address, config.ports)).fuse();
-
-    futures::pin_mut!(signals_task, https_server, http_server);
-    futures::select! {
-        _ = https_server => {},
-        _ = http_server => {},
-        _ = signals_task => {},
+    let mut https_servers = config
+        .address
+        .with_port(config.ports.https)?
+        .into_iter()
+        .map(|addr| {
+            tracing::debug!("https listening on {}", addr);
+            let acceptor = RustlsAcceptor::new(tls_config.clone()).acceptor(limits.clone());
+            axum_server::bind(addr)
+                .acceptor(acceptor)
+                .handle(server_handle.clone())
+                .serve(app.clone().into_make_service())
+        })
+        .collect::<FuturesUnordered<_>>();
+    let trusted_proxies = Arc::new(config.trusted_proxies);
+    let mut http_servers = config
+        .address
+        .with_port(config.ports.http)?
+        .into_iter()
+        .map(|addr| {
+            tokio::spawn(redirect_http_to_https(
+                addr,
+                config.ports,
+                app.clone(),
+                trusted_proxies.clone(),
+                limits.clone(),
+            ))
+        })
+        .collect::<FuturesUnordered<_>>();
+
+    // The first listener to stop ends the service, so that a listener that
+    // fails, e.g. because its address is in use, is reported and retried.
+    futures::pin_mut!(signals_task);
+    let result = futures::select! {
+        result = https_servers.select_next_some() => {
+            result.context("https listener failed")
+        },
+        result = http_servers.select_next_some() => match result {
+            Ok(result) => result.context("http listener failed"),
+            Err(error) => Err(error).context("http listener panicked"),
+        },
+        _ = signals_task => Ok(()),
     };
 
+    for http_server in http_servers.iter() {
+        http_server.abort();
+    }
     signals_handle.close();
-    Ok(())
+    result
 }
@@ -0,0 +1,233 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::HashMap,
+    io,
+    net::IpAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use axum_server::accept::Accept;
+use futures::future::BoxFuture;
+use hyper::server::conn::AddrStream;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    sync::{OwnedSemaphorePermit, Semaphore},
+};
+
+/// Limits on the connections accepted by the listeners, shared by all of
+/// them. Small embedded hosts have few file descriptors to spare, so one
+/// misbehaving poller should not be able to take all of them.
+#[derive(Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct ConnectionLimits {
+    /// Maximum number of connections open at once, across all listeners.
+    #[serde(rename = "max-connections")]
+    max_connections: Option<usize>,
+    /// Maximum number of connections open at once from a single address.
+    #[serde(rename = "max-connections-per-ip")]
+    max_connections_per_ip: Option<usize>,
+    /// Seconds a new connection waits for a free slot when the server is at
+    /// `max-connections`, before it is dropped.
+    #[serde(rename = "accept-timeout")]
+    accept_timeout: u64,
+    /// Maximum number of new connections waiting for a free slot at once.
+    /// Connections beyond these are refused immediately.
+    #[serde(rename = "accept-queue")]
+    accept_queue: usize,
+}
+
+/// Counts open connections per peer address. Entries are removed when the
+/// last connection from an address closes.
+#[derive(Default)]
+struct PeerCounts(Mutex<HashMap<IpAddr, usize>>);
+
+struct PeerGuard {
+    counts: Arc<PeerCounts>,
+    address: IpAddr,
+}
+
+impl PeerCounts {
+    fn acquire(self: &Arc<Self>, address: IpAddr, limit: usize) -> Option<PeerGuard> {
+        let mut counts = self.0.lock().unwrap();
+        let count = counts.entry(address).or_default();
+        if *count >= limit {
+            return None;
+        }
+        *count += 1;
+        Some(PeerGuard {
+            counts: self.clone(),
+            address,
+        })
+    }
+}
+
+impl Drop for PeerGuard {
+    fn drop(&mut self) {
+        let mut counts = self.counts.0.lock().unwrap();
+        if let Some(count) = counts.get_mut(&self.address) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&self.address);
+            }
+        }
+    }
+}
+
+/// Acceptor that enforces [ConnectionLimits] before handing the connection to
+/// the inner acceptor (i.e. before the TLS handshake).
+#[derive(Clone)]
+pub(crate) struct LimitAcceptor<A> {
+    inner: A,
+    permits: Option<Arc<Semaphore>>,
+    queue: Arc<Semaphore>,
+    peers: Arc<PeerCounts>,
+    max_connections_per_ip: Option<usize>,
+    accept_timeout: Duration,
+}
+
+impl<A> LimitAcceptor<A> {
+    pub fn new(inner: A, limits: &ConnectionLimits) -> Self {
+        LimitAcceptor {
+            inner,
+            permits: limits
+                .max_connections
+                .map(|limit| Arc::new(Semaphore::new(limit))),
+            queue: Arc::new(Semaphore::new(limits.accept_queue)),
+            peers: Arc::default(),
+            max_connections_per_ip: limits.max_connections_per_ip,
+            accept_timeout: Duration::from_secs(limits.accept_timeout),
+        }
+    }
+}
+
+fn refused(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionRefused, reason.to_string())
+}
+
+impl<A, S> Accept<AddrStream, S> for LimitAcceptor<A>
+where
+    A: Accept<LimitedStream<AddrStream>, S> + Clone + Send + Sync + 'static,
+    A::Future: Send,
+    S: Send + 'static,
+{
+    type Stream = A::Stream;
+    type Service = A::Service;
+    type Future = BoxFuture<'static, io::Result<(Self::Stream, Self::Service)>>;
+
+    fn accept(&self, stream: AddrStream, service: S) -> Self::Future {
+        let acceptor = self.clone();
+        Box::pin(async move {
+            let address = stream.remote_addr().ip();
+            let peer = match acceptor.max_connections_per_ip {
+                Some(limit) => match acceptor.peers.acquire(address, limit) {
+                    Some(guard) => Some(guard),
+                    None => {
+                        tracing::warn!(%address, "per-address connection limit reached");
+                        return Err(refused("per-address connection limit reached"));
+                    }
+                },
+                None => None,
+            };
+
+            let permit = match acceptor.permits {
+                Some(permits) => match permits.clone().try_acquire_owned() {
+                    Ok(permit) => Some(permit),
+                    // Wait for a free slot, holding a place in the queue.
+                    Err(_) => match acceptor.queue.clone().try_acquire_owned() {
+                        Ok(_queued) => {
+                            let permit = tokio::time::timeout(
+                                acceptor.accept_timeout,
+                                permits.acquire_owned(),
+                            )
+                            .await;
+                            match permit {
+                                Ok(Ok(permit)) => Some(permit),
+                                _ => {
+                                    tracing::warn!(%address, "connection limit reached");
+                                    return Err(refused("connection limit reached"));
+                                }
+                            }
+                        }
+                        Err(_) => {
+                            tracing::warn!(%address, "connection limit reached, queue full");
+                            return Err(refused("connection limit reached, queue full"));
+                        }
+                    },
+                },
+                None => None,
+            };
+
+            let stream = LimitedStream {
+                inner: stream,
+                _permit: permit,
+                _peer: peer,
+            };
+            acceptor.inner.accept(stream, service).await
+        })
+    }
+}
+
+/// A connection holding its share of the limits until it is dropped.
+pub(crate) struct LimitedStream<I> {
+    inner: I,
+    _permit: Option<OwnedSemaphorePermit>,
+    _peer: Option<PeerGuard>,
+}
+
+impl<I: AsyncRead + Unpin> AsyncRead for LimitedStream<I> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<I: AsyncWrite + Unpin> AsyncWrite for LimitedStream<I> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
@@ -0,0 +1,185 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::{self, Body, Full},
+    extract::{OriginalUri, Path, State},
+    http::{header, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{get, MethodRouter},
+    Json,
+};
+use redfish_codegen::registries::base::v1_15_0::Base;
+use serde_json::json;
+use seuss::redfish_error;
+
+use crate::links;
+
+/// A parameter of an action, as listed in its ActionInfo.
+pub struct Parameter {
+    pub name: &'static str,
+    pub required: bool,
+    pub data_type: &'static str,
+    pub allowable_values: &'static [&'static str],
+}
+
+/// The parameters of an action, served by [info] at `id` below the resource
+/// the action belongs to, e.g. ResetActionInfo.
+pub struct ActionInfo {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub parameters: &'static [Parameter],
+}
+
+impl ActionInfo {
+    fn to_json(&self, odata_id: &str) -> serde_json::Value {
+        let parameters: Vec<_> = self
+            .parameters
+            .iter()
+            .map(|parameter| {
+                json!({
+                    "Name": parameter.name,
+                    "Required": parameter.required,
+                    "DataType": parameter.data_type,
+                    "AllowableValues": parameter.allowable_values,
+                })
+            })
+            .collect();
+        json!({
+            "@odata.id": odata_id,
+            "@odata.type": "#ActionInfo.v1_3_0.ActionInfo",
+            "Id": self.id,
+            "Name": self.name,
+            "Parameters": parameters,
+        })
+    }
+}
+
+fn not_allowed(info: Option<String>) -> Response {
+    let mut body = json!(redfish_error::one_message(Base::OperationNotAllowed.into()));
+    if let Some(info) = info {
+        body["@Redfish.ActionInfo"] = info.into();
+    }
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        [(header::ALLOW, HeaderValue::from_static("POST"))],
+        Json(body),
+    )
+        .into_response()
+}
+
+/// Routes the target of an action. Actions are only invoked with POST, so
+/// any other method, e.g. a GET from a client exploring the service, is
+/// answered with a Redfish error rather than an empty 405.
+pub fn target<S>(router: MethodRouter<S>) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.fallback(|| async { not_allowed(None) })
+}
+
+/// Like [target], for an action with an ActionInfo. The error refers the
+/// client to the ActionInfo, to learn how to invoke the action.
+pub fn target_with_info<S>(router: MethodRouter<S>, info: &'static ActionInfo) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.fallback(move |OriginalUri(uri): OriginalUri| async move {
+        let resource = uri.path().split("/Actions/").next().unwrap_or_default();
+        not_allowed(Some(format!("{}/{}", resource, info.id)))
+    })
+}
+
+/// Serves `info` below the resource captured by the route, if `exists`
+/// finds the resource.
+pub fn info<F>(info: &'static ActionInfo, exists: F) -> MethodRouter
+where
+    F: Fn(&str) -> bool + Clone + Send + Sync + 'static,
+{
+    get(
+        move |OriginalUri(uri): OriginalUri, Path(id): Path<String>| async move {
+            if !exists(&id) {
+                let message = Base::ResourceNotFound("ActionInfo".to_string(), info.id.to_string());
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(redfish_error::one_message(message.into())),
+                )
+                    .into_response();
+            }
+            Json(info.to_json(uri.path())).into_response()
+        },
+    )
+}
+
+/// An action of a resource, completed by [advertise] with what the generated
+/// model of the resource has no properties for: OEM actions, and the
+/// @Redfish.ActionInfo annotation.
+pub struct Action {
+    /// The name of the action, e.g. TwardyEce.Restore.
+    pub name: &'static str,
+    /// Whether the action is listed under Actions/Oem.
+    pub oem: bool,
+    /// The ActionInfo served below the resource for the action, if any.
+    pub info: Option<&'static ActionInfo>,
+}
+
+/// Adds `actions` to the Actions of the resource in the successful responses
+/// that represent it, each with its target and ActionInfo below the
+/// @odata.id of the resource.
+pub async fn advertise(
+    State(actions): State<&'static [Action]>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let response = next.run(request).await;
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let mut resource: serde_json::Value = match serde_json::from_slice(&bytes) {
+        Ok(resource) => resource,
+        Err(_) => return Response::from_parts(parts, body::boxed(Full::from(bytes))),
+    };
+    let odata_id = match resource.get("@odata.id").and_then(|id| id.as_str()) {
+        Some(odata_id) => odata_id.to_string(),
+        None => return Response::from_parts(parts, body::boxed(Full::from(bytes))),
+    };
+    for action in actions {
+        let key = "#".to_string() + action.name;
+        let (advertised, path) = if action.oem {
+            let path = "Oem/".to_string() + action.name;
+            (&mut resource["Actions"]["Oem"][&key], path)
+        } else {
+            (&mut resource["Actions"][&key], action.name.to_string())
+        };
+        if advertised.get("target").is_none() {
+            advertised["target"] = links::action(&odata_id, &path).into();
+        }
+        if let Some(info) = action.info {
+            advertised["@Redfish.ActionInfo"] = format!("{}/{}", odata_id, info.id).into();
+        }
+    }
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let body = serde_json::to_vec(&resource).unwrap_or_default();
+    Response::from_parts(parts, body::boxed(Full::from(body)))
+}
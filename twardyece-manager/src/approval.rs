@@ -0,0 +1,421 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, request::Parts, HeaderMap, HeaderValue, Method, Request, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use redfish_codegen::models::redfish;
+use redfish_codegen::registries::base::v1_15_0::Base;
+use serde_json::json;
+use seuss::{
+    auth::{AuthenticateRequest, AuthenticatedUser, Role},
+    redfish_error,
+};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tower::ServiceExt;
+
+use crate::{
+    links,
+    openapi::{self, Routes},
+};
+
+#[derive(serde::Deserialize)]
+#[serde(default)]
+pub struct Configuration {
+    /// ResetTypes of ComputerSystem.Reset that need a second user's
+    /// approval, e.g. ForceOff.
+    #[serde(rename = "reset-types")]
+    reset_types: Vec<String>,
+    /// Whether deleting a system needs a second user's approval.
+    #[serde(rename = "delete-systems")]
+    delete_systems: bool,
+    /// Seconds a request waits for approval before it is discarded.
+    timeout: u64,
+    /// Requests that may wait for approval at once. Further requests that
+    /// need approval are refused until one is approved, discarded or
+    /// expires.
+    #[serde(rename = "max-pending")]
+    max_pending: usize,
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Configuration {
+            reset_types: vec!["ForceOff".to_string()],
+            delete_systems: true,
+            timeout: 300,
+            max_pending: 16,
+        }
+    }
+}
+
+/// A destructive request waiting for a second user to approve it.
+struct Pending {
+    requester: String,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+    expires: Instant,
+}
+
+#[derive(Default)]
+struct Requests {
+    next_id: u64,
+    pending: BTreeMap<u64, Pending>,
+}
+
+impl Requests {
+    fn prune(&mut self) {
+        let now = Instant::now();
+        self.pending.retain(|id, pending| {
+            let expired = pending.expires <= now;
+            if expired {
+                tracing::info!("approval {} expired", id);
+            }
+            !expired
+        });
+    }
+}
+
+/// Destructive requests held until a second privileged user approves them.
+#[derive(Clone)]
+pub struct Approvals<A> {
+    config: Arc<Configuration>,
+    authenticator: A,
+    link: links::LinkBuilder,
+    requests: Arc<Mutex<Requests>>,
+}
+
+fn error(status: StatusCode, message: Base) -> Response {
+    (status, Json(redfish_error::one_message(message.into()))).into_response()
+}
+
+/// The part of `path` below the system it addresses, which is empty for the
+/// system itself, or None if `path` is not within a system.
+fn below_system(path: &str) -> Option<&str> {
+    let path = path.strip_prefix(links::SYSTEMS)?.strip_prefix('/')?;
+    let (id, rest) = path.split_once('/').unwrap_or((path, ""));
+    (!id.is_empty()).then_some(rest)
+}
+
+impl<A> Approvals<A>
+where
+    A: AuthenticateRequest + Clone + Send + 'static,
+{
+    pub fn new(config: Configuration, authenticator: A, link: links::LinkBuilder) -> Self {
+        Approvals {
+            config: Arc::new(config),
+            authenticator,
+            link,
+            requests: Arc::new(Mutex::new(Requests::default())),
+        }
+    }
+
+    /// Resolves the user making the request the way seuss does for the rest
+    /// of the service, so that sessions work as well as Basic
+    /// authentication. Approving, and requesting operations that need
+    /// approval, takes the ConfigureComponents privilege of Operators and
+    /// Administrators.
+    fn authenticate(
+        &self,
+        parts: &mut Parts,
+    ) -> Result<AuthenticatedUser, (StatusCode, redfish::Error)> {
+        let user = match self.authenticator.authenticate_request(parts) {
+            Ok(Some(user)) => user,
+            Ok(None) | Err(_) => {
+                let error = redfish_error::one_message(Base::NoValidSession.into());
+                return Err((StatusCode::UNAUTHORIZED, error));
+            }
+        };
+        if user.role == Role::ReadOnly {
+            let error = redfish_error::one_message(Base::InsufficientPrivilege.into());
+            return Err((StatusCode::FORBIDDEN, error));
+        }
+        Ok(user)
+    }
+
+    /// ResetTypes of ComputerSystem.Reset that are held for approval.
+    pub fn reset_types(&self) -> &[String] {
+        &self.config.reset_types
+    }
+
+    fn needs_approval(&self, method: &Method, path: &str, body: &[u8]) -> bool {
+        match below_system(path) {
+            Some("") => *method == Method::DELETE && self.config.delete_systems,
+            Some("Actions/ComputerSystem.Reset") if *method == Method::POST => {
+                serde_json::from_slice::<serde_json::Value>(body)
+                    .ok()
+                    .and_then(|body| body.get("ResetType")?.as_str().map(String::from))
+                    .is_some_and(|reset_type| self.config.reset_types.contains(&reset_type))
+            }
+            _ => false,
+        }
+    }
+
+    fn describe(&self, id: u64, pending: &Pending) -> serde_json::Value {
+        let odata_id = self.link.id(links::APPROVALS).0 + "/" + &id.to_string();
+        json!({
+            "@odata.id": odata_id,
+            "Id": id.to_string(),
+            "RequestedBy": pending.requester,
+            "Method": pending.method.as_str(),
+            "Target": self.link.id(pending.uri.path()).0,
+            "Parameters": serde_json::from_slice::<serde_json::Value>(&pending.body).ok(),
+            "SecondsRemaining": pending
+                .expires
+                .saturating_duration_since(Instant::now())
+                .as_secs(),
+            "Actions": {
+                "#Approval.Approve": {
+                    "target": links::action(&odata_id, "Approve"),
+                },
+            },
+        })
+    }
+
+    /// Holds the request instead of executing it, answering with the
+    /// approval it now waits for.
+    fn hold(&self, mut parts: Parts, body: Bytes) -> Response {
+        let user = match self.authenticate(&mut parts) {
+            Ok(user) => user,
+            Err((status, error)) => return (status, Json(error)).into_response(),
+        };
+        let pending = Pending {
+            requester: user.username,
+            method: parts.method,
+            uri: parts.uri,
+            body,
+            expires: Instant::now() + Duration::from_secs(self.config.timeout),
+        };
+        let mut requests = self.requests.lock().unwrap();
+        requests.prune();
+        if requests.pending.len() >= self.config.max_pending {
+            // Retry once the oldest pending request has expired.
+            let retry_after = requests
+                .pending
+                .values()
+                .map(|pending| pending.expires)
+                .min()
+                .map_or(0, |expires| {
+                    expires.saturating_duration_since(Instant::now()).as_secs()
+                })
+                + 1;
+            tracing::warn!(
+                "{} {} by {} refused: {} requests await approval",
+                pending.method,
+                pending.uri,
+                pending.requester,
+                requests.pending.len()
+            );
+            let message = Base::ServiceTemporarilyUnavailable(retry_after.to_string());
+            let mut response = error(StatusCode::SERVICE_UNAVAILABLE, message);
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            return response;
+        }
+        let id = requests.next_id;
+        requests.next_id += 1;
+        tracing::warn!(
+            "{} {} by {} awaits approval {}",
+            pending.method,
+            pending.uri,
+            pending.requester,
+            id
+        );
+        let approval = self.describe(id, &pending);
+        requests.pending.insert(id, pending);
+
+        let mut response = (StatusCode::ACCEPTED, Json(approval.clone())).into_response();
+        if let Some(location) = approval["@odata.id"]
+            .as_str()
+            .and_then(|location| HeaderValue::from_str(location).ok())
+        {
+            response.headers_mut().insert(header::LOCATION, location);
+        }
+        response
+    }
+
+    fn list(&self) -> Response {
+        let mut requests = self.requests.lock().unwrap();
+        requests.prune();
+        let members: Vec<_> = requests
+            .pending
+            .keys()
+            .map(|id| json!({ "@odata.id": self.link.id(links::APPROVALS).0 + "/" + &id.to_string() }))
+            .collect();
+        Json(json!({
+            "@odata.id": self.link.id(links::APPROVALS).0,
+            "Name": "Pending Approvals",
+            "Members@odata.count": members.len(),
+            "Members": members,
+        }))
+        .into_response()
+    }
+
+    fn get(&self, id: u64) -> Option<Response> {
+        let mut requests = self.requests.lock().unwrap();
+        requests.prune();
+        let pending = requests.pending.get(&id)?;
+        Some(Json(self.describe(id, pending)).into_response())
+    }
+
+    fn discard(&self, id: u64, user: &AuthenticatedUser) -> Option<Response> {
+        let mut requests = self.requests.lock().unwrap();
+        requests.prune();
+        requests.pending.remove(&id)?;
+        tracing::info!("approval {} discarded by {}", id, user.username);
+        Some(StatusCode::NO_CONTENT.into_response())
+    }
+
+    /// Executes the request held by approval `id` through `app`, with the
+    /// credentials of the approving user.
+    async fn approve(
+        self,
+        app: Router,
+        id: u64,
+        user: AuthenticatedUser,
+        headers: HeaderMap,
+    ) -> Option<Response> {
+        let pending = {
+            let mut requests = self.requests.lock().unwrap();
+            requests.prune();
+            match requests.pending.get(&id) {
+                Some(pending) if pending.requester == user.username => {
+                    return Some(error(StatusCode::FORBIDDEN, Base::InsufficientPrivilege))
+                }
+                Some(_) => requests.pending.remove(&id)?,
+                None => return None,
+            }
+        };
+        tracing::warn!(
+            "{} {} by {} approved by {}",
+            pending.method,
+            pending.uri,
+            pending.requester,
+            user.username
+        );
+
+        let mut request = Request::new(Body::from(pending.body));
+        *request.method_mut() = pending.method;
+        *request.uri_mut() = pending.uri;
+        for (name, value) in headers.iter() {
+            if name != header::CONTENT_LENGTH && name != header::CONTENT_TYPE {
+                request.headers_mut().insert(name, value.clone());
+            }
+        }
+        request.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        Some(app.oneshot(request).await.into_response())
+    }
+
+    /// Serves the approvals below APPROVALS, where `path` is the rest of the
+    /// path after it.
+    async fn serve(self, app: Router, request: Request<Body>, path: &str) -> Response {
+        let (mut parts, _) = request.into_parts();
+        let user = match self.authenticate(&mut parts) {
+            Ok(user) => user,
+            Err((status, error)) => return (status, Json(error)).into_response(),
+        };
+        let method = parts.method;
+        if path.is_empty() {
+            return match method {
+                Method::GET => self.list(),
+                _ => error(StatusCode::METHOD_NOT_ALLOWED, Base::OperationNotAllowed),
+            };
+        }
+
+        let path = match path.strip_prefix('/') {
+            Some(path) => path,
+            None => return StatusCode::NOT_FOUND.into_response(),
+        };
+        let (segment, action) = path.split_once('/').unwrap_or((path, ""));
+        let not_found = || {
+            error(
+                StatusCode::NOT_FOUND,
+                Base::ResourceNotFound("Approval".to_string(), segment.to_string()),
+            )
+        };
+        let id = match segment.parse() {
+            Ok(id) => id,
+            Err(_) => return not_found(),
+        };
+        let response = match (method, action) {
+            (Method::GET, "") => self.get(id),
+            (Method::DELETE, "") => self.discard(id, &user),
+            (Method::POST, "Actions/Approve") => self.approve(app, id, user, parts.headers).await,
+            (_, "") | (_, "Actions/Approve") => Some(error(
+                StatusCode::METHOD_NOT_ALLOWED,
+                Base::OperationNotAllowed,
+            )),
+            _ => None,
+        };
+        response.unwrap_or_else(not_found)
+    }
+}
+
+/// Describes the routes served by [confirm] below APPROVALS.
+pub fn describe(routes: Routes) -> Routes {
+    let approval = links::members(links::APPROVALS, "id");
+    routes
+        .describe(links::APPROVALS, openapi::GET)
+        .describe(&approval, openapi::GET_DELETE)
+        .describe(&links::action(&approval, "Approve"), openapi::POST)
+}
+
+/// Holds destructive requests, such as deleting a system or forcing it off,
+/// until a second privileged user approves them within the timeout. The
+/// pending approvals are served below APPROVALS. Approved requests are
+/// executed through `app`.
+pub async fn confirm<A>(
+    app: Router,
+    approvals: Approvals<A>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response
+where
+    A: AuthenticateRequest + Clone + Send + 'static,
+{
+    let path = request.uri().path().to_string();
+    if let Some(rest) = path.strip_prefix(links::APPROVALS) {
+        if rest.is_empty() || rest.starts_with('/') {
+            return approvals.serve(app, request, rest).await;
+        }
+    }
+    if below_system(&path).is_none() || request.method() == Method::GET {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    if approvals.needs_approval(&parts.method, &path, &body) {
+        return approvals.hold(parts, body);
+    }
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
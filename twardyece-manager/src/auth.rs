@@ -14,21 +14,142 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use axum::http::{header, HeaderMap};
+use base64::Engine;
 use redfish_codegen::models::redfish;
+#[cfg(feature = "pam")]
+use seuss::auth::pam::LinuxPamAuthenticator;
 use seuss::auth::{AuthenticatedUser, BasicAuthentication, Role};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 
+mod cache;
+pub use cache::*;
+
+mod password_file;
+pub use password_file::*;
+
+mod pool;
+pub use pool::*;
+
+mod read_only;
+pub use read_only::*;
+
+mod static_accounts;
+pub use static_accounts::*;
+
+#[derive(Default, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Backend {
+    /// Authenticate against PAM, mapping groups to roles with the role-map.
+    /// Only available when built with the `pam` feature.
+    #[default]
+    Pam,
+    /// Authenticate against an htpasswd-style file.
+    PasswordFile { path: String },
+    /// Authenticate a fixed set of accounts, for testing.
+    Static { accounts: Vec<StaticAccount> },
+}
+
+#[derive(serde::Deserialize)]
+#[serde(default)]
+pub struct Configuration {
+    pub backend: Backend,
+    /// Seconds for which a successful Basic authentication is remembered.
+    /// Caching is disabled when this is zero.
+    #[serde(rename = "cache-ttl")]
+    pub cache_ttl: u64,
+    /// Number of threads dedicated to running the authentication backend.
+    pub workers: usize,
+    /// Authentications allowed to wait for a worker before requests are
+    /// turned away.
+    #[serde(rename = "queue-depth")]
+    pub queue_depth: usize,
+    /// Seconds to wait for the backend before failing the request.
+    pub timeout: u64,
+    /// Realm of the Basic challenge sent with 401 responses.
+    pub realm: String,
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Configuration {
+            backend: Backend::default(),
+            cache_ttl: 0,
+            workers: 4,
+            queue_depth: 32,
+            timeout: 10,
+            realm: "twardyece-manager".to_string(),
+        }
+    }
+}
+
+/// Extracts the username and password from a Basic Authorization header.
+pub fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let encoded = value.strip_prefix("Basic ")?;
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+    Some((username.to_string(), password.to_string()))
+}
+
+/// A salted digest of a username and password, so that credentials can be
+/// remembered without keeping the password in memory.
+fn digest(salt: &[u8; 16], username: &str, password: &str) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(salt)
+        .chain_update(username)
+        .chain_update([0])
+        .chain_update(password)
+        .finalize()
+        .into()
+}
+
+/// The authentication backend selected in the configuration.
 #[derive(Clone)]
-pub struct ExampleBasicAuthenticator;
+pub enum Authenticator {
+    #[cfg(feature = "pam")]
+    Pam(LinuxPamAuthenticator),
+    PasswordFile(PasswordFileAuthenticator),
+    Static(StaticAuthenticator),
+}
+
+impl Authenticator {
+    pub fn new(backend: Backend, role_map: HashMap<Role, String>) -> anyhow::Result<Self> {
+        match backend {
+            #[cfg(feature = "pam")]
+            Backend::Pam => Ok(Authenticator::Pam(LinuxPamAuthenticator::new(role_map)?)),
+            #[cfg(not(feature = "pam"))]
+            Backend::Pam => {
+                let _ = role_map;
+                anyhow::bail!("built without PAM support, use the password-file backend")
+            }
+            Backend::PasswordFile { path } => Ok(Authenticator::PasswordFile(
+                PasswordFileAuthenticator::new(path)?,
+            )),
+            Backend::Static { accounts } => {
+                Ok(Authenticator::Static(StaticAuthenticator::new(accounts)))
+            }
+        }
+    }
+}
 
-impl BasicAuthentication for ExampleBasicAuthenticator {
+impl BasicAuthentication for Authenticator {
     fn authenticate(
         &self,
         username: String,
-        _password: String,
+        password: String,
     ) -> Result<AuthenticatedUser, redfish::Error> {
-        Ok(AuthenticatedUser {
-            username: username.to_string(),
-            role: Role::Administrator,
-        })
+        match self {
+            #[cfg(feature = "pam")]
+            Authenticator::Pam(authenticator) => authenticator.authenticate(username, password),
+            Authenticator::PasswordFile(authenticator) => {
+                authenticator.authenticate(username, password)
+            }
+            Authenticator::Static(authenticator) => authenticator.authenticate(username, password),
+        }
     }
 }
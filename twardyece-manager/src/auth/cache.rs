@@ -0,0 +1,163 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rand::RngCore;
+use redfish_codegen::models::redfish;
+use seuss::auth::{AuthenticatedUser, BasicAuthentication, Role};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Counters describing how effective the authentication cache is.
+#[derive(Default)]
+pub struct AuthMetrics {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    failures: AtomicU64,
+    backend_micros: AtomicU64,
+}
+
+impl AuthMetrics {
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// Total time spent in the wrapped authenticator.
+    pub fn backend_time(&self) -> Duration {
+        Duration::from_micros(self.backend_micros.load(Ordering::Relaxed))
+    }
+}
+
+struct CacheEntry {
+    digest: [u8; 32],
+    role: Role,
+    expires: Instant,
+}
+
+/// Remembers successful authentications for a fixed time, so that clients
+/// using Basic authentication do not pay for a round trip through a slow
+/// backend (e.g. PAM) on every request. Only a salted digest of the password
+/// is kept in memory.
+#[derive(Clone)]
+pub struct CachingAuthenticator<B>
+where
+    B: BasicAuthentication + Clone,
+{
+    inner: B,
+    ttl: Duration,
+    salt: [u8; 16],
+    entries: Arc<Mutex<HashMap<String, CacheEntry>>>,
+    metrics: Arc<AuthMetrics>,
+}
+
+impl<B> CachingAuthenticator<B>
+where
+    B: BasicAuthentication + Clone,
+{
+    pub fn new(inner: B, ttl: Duration) -> Self {
+        let mut salt = [0; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        CachingAuthenticator {
+            inner,
+            ttl,
+            salt,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            metrics: Arc::new(AuthMetrics::default()),
+        }
+    }
+
+    /// Forgets the authentication of `username`, e.g. when its password has
+    /// changed.
+    pub fn forget(&self, username: &str) {
+        self.entries.lock().unwrap().remove(username);
+    }
+
+    fn lookup(&self, username: &str, digest: &[u8; 32]) -> Option<Role> {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        entries.retain(|_, entry| entry.expires > now);
+        entries
+            .get(username)
+            .filter(|entry| &entry.digest == digest)
+            .map(|entry| entry.role.clone())
+    }
+}
+
+impl<B> BasicAuthentication for CachingAuthenticator<B>
+where
+    B: BasicAuthentication + Clone,
+{
+    fn authenticate(
+        &self,
+        username: String,
+        password: String,
+    ) -> Result<AuthenticatedUser, redfish::Error> {
+        let digest = super::digest(&self.salt, &username, &password);
+        if let Some(role) = self.lookup(&username, &digest) {
+            self.metrics.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(AuthenticatedUser { username, role });
+        }
+
+        self.metrics.misses.fetch_add(1, Ordering::Relaxed);
+        let start = Instant::now();
+        let result = self.inner.authenticate(username.clone(), password);
+        let elapsed = start.elapsed().as_micros().try_into().unwrap_or(u64::MAX);
+        self.metrics
+            .backend_micros
+            .fetch_add(elapsed, Ordering::Relaxed);
+
+        let mut entries = self.entries.lock().unwrap();
+        match &result {
+            Ok(user) if !self.ttl.is_zero() => {
+                entries.insert(
+                    username,
+                    CacheEntry {
+                        digest,
+                        role: user.role.clone(),
+                        expires: Instant::now() + self.ttl,
+                    },
+                );
+            }
+            Ok(_) => {}
+            Err(_) => {
+                self.metrics.failures.fetch_add(1, Ordering::Relaxed);
+                entries.remove(&username);
+            }
+        }
+
+        tracing::debug!(
+            hits = self.metrics.hits(),
+            misses = self.metrics.misses(),
+            failures = self.metrics.failures(),
+            backend_ms = self.metrics.backend_time().as_millis() as u64,
+            "authentication cache"
+        );
+        result
+    }
+}
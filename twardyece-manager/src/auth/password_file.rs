@@ -0,0 +1,324 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use argon2::{
+    password_hash::{PasswordHash, SaltString},
+    Argon2, PasswordHasher, PasswordVerifier,
+};
+use rand::{distributions::Alphanumeric, Rng, RngCore};
+use redfish_codegen::models::redfish;
+use redfish_codegen::registries::base::v1_15_0::Base;
+use seuss::{
+    auth::{AuthenticatedUser, BasicAuthentication, Role},
+    redfish_error,
+};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+/// Marks an account whose password must be changed before it can be used
+/// for anything else, e.g. the one that was provisioned.
+const PASSWORD_CHANGE_REQUIRED: &str = "password-change-required";
+
+struct Account {
+    hash: String,
+    role: Role,
+    password_change_required: bool,
+}
+
+#[derive(Default)]
+struct Accounts {
+    modified: Option<SystemTime>,
+    accounts: HashMap<String, Account>,
+}
+
+/// Parses a password file. Each non-empty line that does not start with `#`
+/// has the form `username:hash:Role`, where hash is a bcrypt or argon2 hash
+/// in its usual string encoding, and Role is one of the Redfish roles. The
+/// line may end with `:password-change-required`.
+fn parse(contents: &str) -> anyhow::Result<HashMap<String, Account>> {
+    let mut accounts = HashMap::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.splitn(4, ':');
+        let (username, hash, role) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(username), Some(hash), Some(role)) => (username, hash, role),
+            _ => anyhow::bail!("line {}: expected username:hash:role", number + 1),
+        };
+        let role: Role = serde_yaml::from_str(role)
+            .with_context(|| format!("line {}: invalid role {}", number + 1, role))?;
+        let password_change_required = match fields.next() {
+            None => false,
+            Some(PASSWORD_CHANGE_REQUIRED) => true,
+            Some(flag) => anyhow::bail!("line {}: unknown flag {}", number + 1, flag),
+        };
+        accounts.insert(
+            username.to_string(),
+            Account {
+                hash: hash.to_string(),
+                role,
+                password_change_required,
+            },
+        );
+    }
+    Ok(accounts)
+}
+
+/// An argon2 hash of `password`, with a random salt.
+fn hash(password: &str) -> anyhow::Result<String> {
+    let mut salt = [0; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let salt = SaltString::encode_b64(&salt).map_err(|error| anyhow::anyhow!(error))?;
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|error| anyhow::anyhow!(error))?;
+    Ok(hash.to_string())
+}
+
+fn random_password() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(24)
+        .map(char::from)
+        .collect()
+}
+
+fn verify(password: &str, hash: &str) -> bool {
+    if hash.starts_with("$argon2") {
+        PasswordHash::new(hash)
+            .map(|hash| {
+                Argon2::default()
+                    .verify_password(password.as_bytes(), &hash)
+                    .is_ok()
+            })
+            .unwrap_or(false)
+    } else {
+        bcrypt::verify(password, hash).unwrap_or(false)
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Writes `contents` to a file next to `path`, readable only by the owner,
+/// so that it can then be renamed over `path` in one step.
+fn stage_private_file(path: &Path, contents: &str) -> anyhow::Result<PathBuf> {
+    let staged = with_suffix(path, ".tmp");
+    // Left behind if a previous attempt was interrupted.
+    let _ = fs::remove_file(&staged);
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&staged)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .with_context(|| format!("failed to create {}", staged.display()))?;
+    Ok(staged)
+}
+
+/// Adds an Administrator account with a random password to the password
+/// file, which holds `contents` and no accounts. The password itself is
+/// written next to the password file, readable only by the owner, so that it
+/// never appears in the logs. It must be changed on first login.
+fn provision(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let password = random_password();
+    let hash = hash(&password)?;
+
+    let mut accounts = contents.to_string();
+    if !accounts.is_empty() && !accounts.ends_with('\n') {
+        accounts.push('\n');
+    }
+    accounts += &format!(
+        "admin:{}:Administrator:{}\n",
+        hash, PASSWORD_CHANGE_REQUIRED
+    );
+
+    // Both files are staged before either is put in place, and the password
+    // is removed again if the account can't be added, so that a failure
+    // never leaves an account behind whose password is lost.
+    let password_path = with_suffix(path, ".initial-password");
+    let staged_password = stage_private_file(&password_path, &(password + "\n"))?;
+    let staged_accounts = match stage_private_file(path, &accounts) {
+        Ok(staged) => staged,
+        Err(error) => {
+            let _ = fs::remove_file(&staged_password);
+            return Err(error);
+        }
+    };
+    if let Err(error) = fs::rename(&staged_password, &password_path) {
+        let _ = fs::remove_file(&staged_password);
+        let _ = fs::remove_file(&staged_accounts);
+        return Err(error).with_context(|| format!("failed to create {}", password_path.display()));
+    }
+    if let Err(error) = fs::rename(&staged_accounts, path) {
+        let _ = fs::remove_file(&password_path);
+        let _ = fs::remove_file(&staged_accounts);
+        return Err(error).with_context(|| format!("failed to write {}", path.display()));
+    }
+
+    tracing::warn!(
+        "created account admin; its initial password is in {}, and must be changed on first login",
+        password_path.display()
+    );
+    Ok(())
+}
+
+/// Authenticates users against an htpasswd-style file, for deployments where
+/// PAM is not available. The file is re-read whenever its modification time
+/// changes, so accounts can be edited without restarting the service.
+#[derive(Clone)]
+pub struct PasswordFileAuthenticator {
+    path: PathBuf,
+    accounts: Arc<Mutex<Accounts>>,
+    /// Verified against for unknown users, so that they take as long to
+    /// turn away as a wrong password, and valid usernames can't be told
+    /// apart by timing.
+    dummy_hash: Arc<String>,
+}
+
+impl PasswordFileAuthenticator {
+    /// If the file does not exist yet, or has no accounts, an initial
+    /// Administrator account is added to it (see [`provision`]).
+    pub fn new(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let authenticator = PasswordFileAuthenticator {
+            path: path.into(),
+            accounts: Arc::new(Mutex::new(Accounts::default())),
+            dummy_hash: Arc::new(hash(&random_password())?),
+        };
+        let contents = match fs::read_to_string(&authenticator.path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("failed to read {}", authenticator.path.display()))
+            }
+        };
+        let accounts =
+            parse(&contents).with_context(|| format!("in {}", authenticator.path.display()))?;
+        if accounts.is_empty() {
+            provision(&authenticator.path, &contents)?;
+        }
+        authenticator.reload(&mut authenticator.accounts.lock().unwrap())?;
+        Ok(authenticator)
+    }
+
+    fn reload(&self, accounts: &mut Accounts) -> anyhow::Result<()> {
+        let modified = fs::metadata(&self.path)?.modified().ok();
+        if accounts.modified.is_some() && accounts.modified == modified {
+            return Ok(());
+        }
+
+        let contents = fs::read_to_string(&self.path)?;
+        accounts.accounts =
+            parse(&contents).with_context(|| format!("in {}", self.path.display()))?;
+        accounts.modified = modified;
+        tracing::info!("loaded password file {}", self.path.display());
+        Ok(())
+    }
+
+    /// The accounts, re-read if the file has changed.
+    fn accounts(&self) -> MutexGuard<'_, Accounts> {
+        let mut accounts = self.accounts.lock().unwrap();
+        if let Err(error) = self.reload(&mut accounts) {
+            // Keep serving the last good set of accounts.
+            tracing::error!("failed to reload password file: {:#}", error);
+        }
+        accounts
+    }
+
+    pub fn exists(&self, username: &str) -> bool {
+        self.accounts().accounts.contains_key(username)
+    }
+
+    /// Whether `username` must change its password before doing anything
+    /// else.
+    pub fn password_change_required(&self, username: &str) -> bool {
+        self.accounts()
+            .accounts
+            .get(username)
+            .is_some_and(|account| account.password_change_required)
+    }
+
+    /// Replaces the password of `username`, which no longer needs to be
+    /// changed afterwards.
+    pub fn change_password(&self, username: &str, password: &str) -> anyhow::Result<()> {
+        let hash = hash(password)?;
+        let mut accounts = self.accounts();
+        let contents = fs::read_to_string(&self.path)
+            .with_context(|| format!("failed to read {}", self.path.display()))?;
+        let mut changed = false;
+        let mut lines = Vec::new();
+        for line in contents.lines() {
+            let mut fields = line.trim().splitn(4, ':');
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(name), Some(_), Some(role)) if name == username => {
+                    lines.push(format!("{}:{}:{}", username, hash, role));
+                    changed = true;
+                }
+                _ => lines.push(line.to_string()),
+            }
+        }
+        if !changed {
+            anyhow::bail!("no account {} in {}", username, self.path.display());
+        }
+
+        let staged = stage_private_file(&self.path, &(lines.join("\n") + "\n"))?;
+        if let Err(error) = fs::rename(&staged, &self.path) {
+            let _ = fs::remove_file(&staged);
+            return Err(error).with_context(|| format!("failed to write {}", self.path.display()));
+        }
+        accounts.modified = None;
+        self.reload(&mut accounts)
+    }
+}
+
+impl BasicAuthentication for PasswordFileAuthenticator {
+    fn authenticate(
+        &self,
+        username: String,
+        password: String,
+    ) -> Result<AuthenticatedUser, redfish::Error> {
+        let accounts = self.accounts();
+        match accounts.accounts.get(&username) {
+            Some(account) if verify(&password, &account.hash) => Ok(AuthenticatedUser {
+                username,
+                role: account.role.clone(),
+            }),
+            Some(_) => Err(redfish_error::one_message(Base::NoValidSession.into())),
+            None => {
+                verify(&password, &self.dummy_hash);
+                Err(redfish_error::one_message(Base::NoValidSession.into()))
+            }
+        }
+    }
+}
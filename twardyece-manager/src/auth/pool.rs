@@ -0,0 +1,264 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rand::RngCore;
+use redfish_codegen::models::redfish;
+use redfish_codegen::registries::base::v1_15_0::Base;
+use seuss::{
+    auth::{AuthenticatedUser, BasicAuthentication},
+    redfish_error,
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU8, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+use tokio::sync::oneshot;
+
+type Verdict = Result<AuthenticatedUser, redfish::Error>;
+
+// The states of a job. The worker and the request waiting on it both move
+// the job out of QUEUED or RUNNING, so they agree on which of them gave up.
+const QUEUED: u8 = 0;
+const RUNNING: u8 = 1;
+const DONE: u8 = 2;
+/// The request gave up before a worker took the job, so it is skipped.
+const ABANDONED: u8 = 3;
+/// The request gave up on the backend and started another worker, so the
+/// one running the job exits when the backend returns.
+const REPLACED: u8 = 4;
+
+struct Job {
+    username: String,
+    password: String,
+    state: Arc<AtomicU8>,
+    reply: oneshot::Sender<Verdict>,
+}
+
+fn work<B>(inner: B, queue: Arc<Mutex<mpsc::Receiver<Job>>>, hung: Arc<AtomicUsize>)
+where
+    B: BasicAuthentication,
+{
+    loop {
+        let job = match queue.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        if job
+            .state
+            .compare_exchange(QUEUED, RUNNING, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            continue;
+        }
+        let result = inner.authenticate(job.username, job.password);
+        if job.state.swap(DONE, Ordering::SeqCst) == REPLACED {
+            hung.fetch_sub(1, Ordering::SeqCst);
+            tracing::info!("replaced authentication worker returned, exiting");
+            return;
+        }
+        let _ = job.reply.send(result);
+    }
+}
+
+/// A verdict kept for the requests in flight with the same credentials.
+struct Prepared {
+    verdict: Verdict,
+    holds: usize,
+}
+
+/// Runs a blocking authenticator (e.g. a PAM conversation) on a dedicated
+/// pool of threads, so that a slow or hung backend ties up at most the pool
+/// instead of the async runtime. Requests are rejected when the queue is full
+/// or the backend does not answer within the timeout. A worker stuck in the
+/// backend past the timeout is replaced, up to the size of the pool.
+///
+/// seuss authenticates synchronously, so the credentials of each request are
+/// authenticated ahead of it with [BlockingPoolAuthenticator::prepare] (see
+/// [crate::middleware::prepare_credentials]), and [BasicAuthentication]
+/// answers with the verdict prepared for them.
+#[derive(Clone)]
+pub struct BlockingPoolAuthenticator {
+    jobs: mpsc::SyncSender<Job>,
+    timeout: Duration,
+    workers: usize,
+    hung: Arc<AtomicUsize>,
+    spawn: Arc<dyn Fn() + Send + Sync>,
+    salt: [u8; 16],
+    verdicts: Arc<Mutex<HashMap<[u8; 32], Prepared>>>,
+}
+
+impl BlockingPoolAuthenticator {
+    pub fn new<B>(inner: B, workers: usize, queue_depth: usize, timeout: Duration) -> Self
+    where
+        B: BasicAuthentication + Clone + Send + 'static,
+    {
+        let (jobs, queue) = mpsc::sync_channel::<Job>(queue_depth);
+        let queue = Arc::new(Mutex::new(queue));
+        let hung = Arc::new(AtomicUsize::new(0));
+        let spawned = AtomicUsize::new(0);
+        let spawn = {
+            let (inner, hung) = (Mutex::new(inner), hung.clone());
+            move || {
+                let inner = inner.lock().unwrap().clone();
+                let (queue, hung) = (queue.clone(), hung.clone());
+                let index = spawned.fetch_add(1, Ordering::SeqCst);
+                thread::Builder::new()
+                    .name(format!("auth-worker-{}", index))
+                    .spawn(move || work(inner, queue, hung))
+                    .expect("failed to spawn authentication worker");
+            }
+        };
+        let workers = workers.max(1);
+        for _ in 0..workers {
+            spawn();
+        }
+
+        let mut salt = [0; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        BlockingPoolAuthenticator {
+            jobs,
+            timeout,
+            workers,
+            hung,
+            spawn: Arc::new(spawn),
+            salt,
+            verdicts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn unavailable(&self) -> redfish::Error {
+        redfish_error::one_message(
+            Base::ServiceTemporarilyUnavailable(self.timeout.as_secs().to_string()).into(),
+        )
+    }
+
+    /// Gives up on the job in `state` after the timeout. If a worker is
+    /// stuck in the backend with it, another is started in its place.
+    fn abandon(&self, state: &AtomicU8) {
+        if state
+            .compare_exchange(QUEUED, ABANDONED, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            return;
+        }
+        if self.hung.fetch_add(1, Ordering::SeqCst) >= self.workers {
+            self.hung.fetch_sub(1, Ordering::SeqCst);
+            tracing::warn!("too many authentication workers are stuck to replace another");
+            return;
+        }
+        match state.compare_exchange(RUNNING, REPLACED, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => {
+                tracing::warn!("authentication worker is stuck, starting another");
+                (self.spawn)();
+            }
+            Err(_) => {
+                self.hung.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// Authenticates on the pool, waiting for the backend without blocking
+    /// the runtime.
+    pub async fn authenticate_async(&self, username: String, password: String) -> Verdict {
+        let (reply, result) = oneshot::channel();
+        let state = Arc::new(AtomicU8::new(QUEUED));
+        let job = Job {
+            username,
+            password,
+            state: state.clone(),
+            reply,
+        };
+        if self.jobs.try_send(job).is_err() {
+            tracing::warn!("authentication queue is full");
+            return Err(self.unavailable());
+        }
+
+        match tokio::time::timeout(self.timeout, result).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => {
+                tracing::error!("authentication worker exited without answering");
+                Err(self.unavailable())
+            }
+            Err(_) => {
+                tracing::warn!("authentication backend timed out");
+                self.abandon(&state);
+                Err(self.unavailable())
+            }
+        }
+    }
+
+    /// Authenticates `username` on the pool, keeping the verdict for
+    /// [BasicAuthentication::authenticate] until the returned guard is
+    /// dropped. Credentials already prepared for another request are not
+    /// authenticated again.
+    pub async fn prepare(&self, username: String, password: String) -> Preparation {
+        let digest = super::digest(&self.salt, &username, &password);
+        let preparation = Preparation {
+            digest,
+            verdicts: self.verdicts.clone(),
+        };
+        if let Some(prepared) = self.verdicts.lock().unwrap().get_mut(&digest) {
+            prepared.holds += 1;
+            return preparation;
+        }
+
+        let verdict = self.authenticate_async(username, password).await;
+        self.verdicts
+            .lock()
+            .unwrap()
+            .entry(digest)
+            .or_insert(Prepared { verdict, holds: 0 })
+            .holds += 1;
+        preparation
+    }
+}
+
+/// Keeps a verdict prepared by [BlockingPoolAuthenticator::prepare].
+pub struct Preparation {
+    digest: [u8; 32],
+    verdicts: Arc<Mutex<HashMap<[u8; 32], Prepared>>>,
+}
+
+impl Drop for Preparation {
+    fn drop(&mut self) {
+        let mut verdicts = self.verdicts.lock().unwrap();
+        if let Some(prepared) = verdicts.get_mut(&self.digest) {
+            prepared.holds -= 1;
+            if prepared.holds == 0 {
+                verdicts.remove(&self.digest);
+            }
+        }
+    }
+}
+
+impl BasicAuthentication for BlockingPoolAuthenticator {
+    fn authenticate(&self, username: String, password: String) -> Verdict {
+        let digest = super::digest(&self.salt, &username, &password);
+        match self.verdicts.lock().unwrap().get(&digest) {
+            Some(prepared) => prepared.verdict.clone(),
+            None => {
+                // Waiting for the backend here would block the runtime.
+                tracing::error!("credentials of {} were not prepared", username);
+                Err(redfish_error::one_message(Base::InternalError.into()))
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use redfish_codegen::models::redfish;
+use seuss::auth::{AuthenticatedUser, BasicAuthentication, Role};
+
+/// Grants the ReadOnly role to users whose role is listed in `roles`, so that
+/// the privilege checks reject any modification they attempt.
+#[derive(Clone)]
+pub struct ReadOnlyAuthenticator<B>
+where
+    B: BasicAuthentication + Clone,
+{
+    inner: B,
+    roles: Vec<Role>,
+}
+
+impl<B> ReadOnlyAuthenticator<B>
+where
+    B: BasicAuthentication + Clone,
+{
+    pub fn new(inner: B, roles: Vec<Role>) -> Self {
+        ReadOnlyAuthenticator { inner, roles }
+    }
+}
+
+impl<B> BasicAuthentication for ReadOnlyAuthenticator<B>
+where
+    B: BasicAuthentication + Clone,
+{
+    fn authenticate(
+        &self,
+        username: String,
+        password: String,
+    ) -> Result<AuthenticatedUser, redfish::Error> {
+        let mut user = self.inner.authenticate(username, password)?;
+        if self.roles.contains(&user.role) {
+            user.role = Role::ReadOnly;
+        }
+        Ok(user)
+    }
+}
@@ -0,0 +1,115 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use redfish_codegen::models::redfish;
+use redfish_codegen::registries::base::v1_15_0::Base;
+use seuss::{
+    auth::{AuthenticatedUser, BasicAuthentication, Role},
+    redfish_error,
+};
+use std::{collections::HashMap, sync::Arc, thread, time::Duration};
+
+/// How authentication of an account fails.
+#[derive(Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Failure {
+    /// As if the password were wrong.
+    InvalidCredentials,
+    /// As if the backend were overloaded.
+    Unavailable,
+    /// As if the backend had failed.
+    InternalError,
+}
+
+fn read_only() -> Role {
+    Role::ReadOnly
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct StaticAccount {
+    pub username: String,
+    /// Compared in plain text. Any password is accepted when this is absent.
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "read_only")]
+    pub role: Role,
+    /// Seconds to wait before answering, e.g. to exercise the timeout of the
+    /// worker pool.
+    #[serde(default)]
+    pub delay: u64,
+    /// Fail every authentication of this account, even with the right
+    /// password.
+    #[serde(default)]
+    pub failure: Option<Failure>,
+}
+
+/// Authenticates a fixed set of accounts with scripted outcomes, so that
+/// behavior depending on authentication can be tested without PAM. Not meant
+/// for production use: passwords are held in plain text.
+#[derive(Clone)]
+pub struct StaticAuthenticator {
+    accounts: Arc<HashMap<String, StaticAccount>>,
+}
+
+impl StaticAuthenticator {
+    pub fn new(accounts: Vec<StaticAccount>) -> Self {
+        tracing::warn!("authenticating against static accounts");
+        StaticAuthenticator {
+            accounts: Arc::new(
+                accounts
+                    .into_iter()
+                    .map(|account| (account.username.clone(), account))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl BasicAuthentication for StaticAuthenticator {
+    fn authenticate(
+        &self,
+        username: String,
+        password: String,
+    ) -> Result<AuthenticatedUser, redfish::Error> {
+        let account = match self.accounts.get(&username) {
+            Some(account) => account,
+            None => return Err(redfish_error::one_message(Base::NoValidSession.into())),
+        };
+        if account.delay > 0 {
+            thread::sleep(Duration::from_secs(account.delay));
+        }
+
+        if let Some(failure) = account.failure {
+            let message = match failure {
+                Failure::InvalidCredentials => Base::NoValidSession,
+                Failure::Unavailable => Base::ServiceTemporarilyUnavailable("1".to_string()),
+                Failure::InternalError => Base::InternalError,
+            };
+            return Err(redfish_error::one_message(message.into()));
+        }
+        if account
+            .password
+            .as_ref()
+            .is_some_and(|expected| *expected != password)
+        {
+            return Err(redfish_error::one_message(Base::NoValidSession.into()));
+        }
+        Ok(AuthenticatedUser {
+            username,
+            role: account.role.clone(),
+        })
+    }
+}
@@ -0,0 +1,285 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::FromRequest,
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use base64::Engine;
+use redfish_codegen::registries::base::v1_15_0::Base;
+use serde_json::{Map, Value};
+use seuss::redfish_error;
+use std::collections::HashMap;
+use tower::ServiceExt;
+
+use crate::links::{self, LinkBuilder};
+
+/// Headers that only concern the connection the batch arrived on, which are
+/// not forwarded to the operations in it.
+const HOP_BY_HOP: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+#[derive(serde::Deserialize)]
+pub struct BatchRequest {
+    requests: Vec<Operation>,
+}
+
+#[derive(serde::Deserialize)]
+struct Operation {
+    id: String,
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    body: Option<serde_json::Value>,
+}
+
+#[derive(serde::Serialize)]
+pub struct BatchResponse {
+    responses: Vec<OperationResult>,
+}
+
+#[derive(serde::Serialize)]
+struct OperationResult {
+    id: String,
+    status: u16,
+    /// A repeated header is given as an array of its values.
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    headers: Map<String, Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<Value>,
+}
+
+impl OperationResult {
+    fn bad_request(id: String, property: &str, value: String) -> Self {
+        let error = redfish_error::one_message(
+            Base::PropertyValueFormatError(value, property.to_string()).into(),
+        );
+        OperationResult {
+            id,
+            status: StatusCode::BAD_REQUEST.as_u16(),
+            headers: Map::new(),
+            body: serde_json::to_value(error).ok(),
+        }
+    }
+}
+
+/// Operations name resources by their @odata.id, which includes the base
+/// path, but paths relative to the base path are accepted too.
+fn target(link: &LinkBuilder, url: &str) -> String {
+    let base_path = link.base_path();
+    let mounted = url
+        .strip_prefix(base_path)
+        .is_some_and(|path| path.is_empty() || path.starts_with('/'));
+    if base_path.is_empty() || mounted {
+        url.to_string()
+    } else {
+        link.id(url).0
+    }
+}
+
+/// The end-to-end headers of the batch request, which are forwarded to every
+/// operation in it, e.g. so that each operation is authenticated as the
+/// client that sent the batch. Those describing the body of the batch are
+/// left out, as each operation has its own.
+fn forwarded(headers: &HeaderMap) -> HeaderMap {
+    let connection: Vec<String> = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect();
+    let mut forwarded = HeaderMap::new();
+    for (name, value) in headers.iter() {
+        let name_str = name.as_str();
+        if HOP_BY_HOP.contains(&name_str)
+            || connection.iter().any(|listed| listed == name_str)
+            || name_str.starts_with("content-")
+        {
+            continue;
+        }
+        forwarded.append(name, value.clone());
+    }
+    forwarded
+}
+
+/// The headers of an operation's response, less Content-Length, which no
+/// longer applies once the body is embedded in the batch response.
+fn response_headers(headers: &HeaderMap) -> Map<String, Value> {
+    let mut result = Map::new();
+    for (name, value) in headers.iter() {
+        if *name == header::CONTENT_LENGTH {
+            continue;
+        }
+        let value = match value.to_str() {
+            Ok(value) => Value::String(value.to_string()),
+            Err(_) => continue,
+        };
+        match result.get_mut(name.as_str()) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(first) => *first = Value::Array(vec![first.take(), value]),
+            None => {
+                result.insert(name.to_string(), value);
+            }
+        }
+    }
+    result
+}
+
+/// The body of an operation's response as the JSON batch format embeds it:
+/// JSON as a value, other text as a string, and anything else as a string
+/// of its base64url encoding.
+fn response_body(headers: &HeaderMap, bytes: Bytes) -> Value {
+    let json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|essence| {
+            let essence = essence.trim();
+            essence == "application/json" || essence.ends_with("+json")
+        });
+    if json {
+        if let Ok(value) = serde_json::from_slice(&bytes) {
+            return value;
+        }
+    }
+    match String::from_utf8(bytes.to_vec()) {
+        Ok(text) => Value::String(text),
+        Err(_) => Value::String(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&bytes)),
+    }
+}
+
+async fn execute(
+    app: Router,
+    link: &LinkBuilder,
+    forwarded: &HeaderMap,
+    operation: Operation,
+) -> OperationResult {
+    let Operation {
+        id,
+        method,
+        url,
+        headers,
+        body,
+    } = operation;
+
+    let method = match Method::from_bytes(method.as_bytes()) {
+        Ok(method) => method,
+        Err(_) => return OperationResult::bad_request(id, "method", method),
+    };
+
+    let mut request = match body {
+        Some(body) => {
+            let mut request = Request::new(Body::from(body.to_string()));
+            request.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            );
+            request
+        }
+        None => Request::new(Body::empty()),
+    };
+    *request.method_mut() = method;
+    *request.uri_mut() = match target(link, &url).parse() {
+        Ok(uri) => uri,
+        Err(_) => return OperationResult::bad_request(id, "url", url),
+    };
+    request.headers_mut().extend(forwarded.clone());
+    // Headers of the operation replace those forwarded from the batch, e.g.
+    // to run an operation with other credentials.
+    for (name, value) in headers.into_iter() {
+        let parsed = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(&value),
+        );
+        match parsed {
+            (Ok(name), Ok(value)) => {
+                request.headers_mut().insert(name, value);
+            }
+            _ => return OperationResult::bad_request(id, "headers", name),
+        }
+    }
+
+    let response = app.oneshot(request).await.into_response();
+    let status = response.status().as_u16();
+    let (parts, body) = response.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(bytes) if !bytes.is_empty() => Some(response_body(&parts.headers, bytes)),
+        _ => None,
+    };
+    let headers = response_headers(&parts.headers);
+
+    OperationResult {
+        id,
+        status,
+        headers,
+        body,
+    }
+}
+
+/// Middleware serving OData JSON batch requests at the $batch resource. Each
+/// operation in the batch is dispatched through `app`, which should be the
+/// fully layered service, in order, and its result is reported in the
+/// response whether or not it succeeded.
+pub async fn batch(
+    app: Router,
+    link: LinkBuilder,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    if request.uri().path() != link.id(links::BATCH).0 {
+        return next.run(request).await;
+    }
+    if request.method() != Method::POST {
+        return (
+            StatusCode::METHOD_NOT_ALLOWED,
+            [(header::ALLOW, HeaderValue::from_static("POST"))],
+            Json(redfish_error::one_message(Base::OperationNotAllowed.into())),
+        )
+            .into_response();
+    }
+
+    let headers = forwarded(request.headers());
+    let batch = match Json::<BatchRequest>::from_request(request, &()).await {
+        Ok(Json(batch)) => batch,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let mut responses = Vec::new();
+    for operation in batch.requests {
+        responses.push(execute(app.clone(), &link, &headers, operation).await);
+    }
+    (
+        [(
+            header::HeaderName::from_static("odata-version"),
+            HeaderValue::from_static("4.0"),
+        )],
+        Json(BatchResponse { responses }),
+    )
+        .into_response()
+}
@@ -0,0 +1,170 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::{Parser, ValueEnum};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::Semaphore;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Authentication {
+    /// Send no credentials. Only useful for anonymous resources.
+    None,
+    /// Send HTTP Basic credentials with every request.
+    Basic,
+    /// Log in once, then send the session token with every request.
+    Session,
+}
+
+/// Measure the throughput of a running manager instance.
+#[derive(Parser)]
+struct Args {
+    /// Base URL of the service, e.g. https://localhost:3001
+    #[clap(value_parser)]
+    url: String,
+    /// Path of the resource to GET
+    #[clap(value_parser, short, long, default_value = "/redfish/v1/Systems")]
+    path: String,
+    /// Total number of requests
+    #[clap(value_parser, short, long, default_value_t = 1000)]
+    requests: usize,
+    /// Number of requests in flight at once
+    #[clap(value_parser, short, long, default_value_t = 16)]
+    concurrency: usize,
+    #[clap(value_enum, short, long, default_value = "basic")]
+    authentication: Authentication,
+    #[clap(value_parser, short, long, default_value = "")]
+    user: String,
+    #[clap(value_parser, long, default_value = "")]
+    password: String,
+    /// Accept self-signed certificates
+    #[clap(short, long)]
+    insecure: bool,
+}
+
+struct Session {
+    token: String,
+    location: Option<String>,
+}
+
+async fn login(client: &reqwest::Client, args: &Args) -> anyhow::Result<Session> {
+    let response = client
+        .post(args.url.clone() + "/redfish/v1/SessionService/Sessions")
+        .json(&serde_json::json!({
+            "UserName": args.user,
+            "Password": args.password,
+        }))
+        .send()
+        .await?
+        .error_for_status()?;
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    Ok(Session {
+        token: header("x-auth-token")
+            .ok_or_else(|| anyhow::anyhow!("no X-Auth-Token in login response"))?,
+        location: header("location"),
+    })
+}
+
+fn request(
+    client: &reqwest::Client,
+    args: &Args,
+    session: Option<&Session>,
+) -> reqwest::RequestBuilder {
+    let request = client.get(args.url.clone() + &args.path);
+    match (args.authentication, session) {
+        (Authentication::Basic, _) => request.basic_auth(&args.user, Some(&args.password)),
+        (Authentication::Session, Some(session)) => request.header("X-Auth-Token", &session.token),
+        _ => request,
+    }
+}
+
+fn percentile(latencies: &[Duration], percentile: usize) -> Duration {
+    let index = (latencies.len() * percentile / 100).min(latencies.len().saturating_sub(1));
+    latencies.get(index).copied().unwrap_or_default()
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Arc::new(Args::parse());
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(args.insecure)
+        .build()?;
+
+    let session = match args.authentication {
+        Authentication::Session => Some(Arc::new(login(&client, &args).await?)),
+        _ => None,
+    };
+
+    let semaphore = Arc::new(Semaphore::new(args.concurrency.max(1)));
+    let start = Instant::now();
+    let mut tasks = Vec::with_capacity(args.requests);
+    for _ in 0..args.requests {
+        let permit = semaphore.clone().acquire_owned().await?;
+        let request = request(&client, &args, session.as_deref());
+        tasks.push(tokio::spawn(async move {
+            let start = Instant::now();
+            let success = match request.send().await {
+                Ok(response) => response.status().is_success() && response.bytes().await.is_ok(),
+                Err(_) => false,
+            };
+            drop(permit);
+            (start.elapsed(), success)
+        }));
+    }
+
+    let mut latencies = Vec::with_capacity(tasks.len());
+    let mut failures = 0;
+    for task in tasks {
+        let (latency, success) = task.await?;
+        latencies.push(latency);
+        if !success {
+            failures += 1;
+        }
+    }
+    let elapsed = start.elapsed();
+    latencies.sort();
+
+    println!("requests:    {}", args.requests);
+    println!("failures:    {}", failures);
+    println!("elapsed:     {:.2?}", elapsed);
+    println!(
+        "throughput:  {:.1} requests/s",
+        args.requests as f64 / elapsed.as_secs_f64()
+    );
+    println!("latency p50: {:.2?}", percentile(&latencies, 50));
+    println!("latency p99: {:.2?}", percentile(&latencies, 99));
+
+    if let Some(location) = session
+        .as_ref()
+        .and_then(|session| session.location.as_ref())
+    {
+        let session = session.as_ref().unwrap();
+        client
+            .delete(args.url.clone() + location)
+            .header("X-Auth-Token", &session.token)
+            .send()
+            .await?;
+    }
+    Ok(())
+}
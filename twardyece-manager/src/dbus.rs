@@ -0,0 +1,229 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{endpoint::Members, events::Events, supervisor::Supervisor};
+
+/// The checks that HTTP requests pass before they can reset a system, which
+/// resets over D-Bus must pass as well.
+#[derive(Clone, Default)]
+pub struct Restrictions {
+    /// The service is in read-only mode, so no system may be reset.
+    pub read_only: bool,
+    /// ResetTypes that need a second user's approval. D-Bus callers can't
+    /// take part in the approval workflow, so these are refused.
+    pub approval_reset_types: Vec<String>,
+}
+
+#[cfg(feature = "dbus")]
+mod service {
+    use redfish_codegen::models::computer_system::v1_20_0::ResetRequestBody;
+    use std::{collections::HashMap, time::Duration};
+    use zbus::{
+        dbus_interface, dbus_proxy, fdo, zvariant::Value, Connection, ConnectionBuilder,
+        MessageHeader,
+    };
+
+    use super::Restrictions;
+    use crate::{
+        endpoint::Members,
+        events::Events,
+        supervisor::{Restart, Supervisor},
+    };
+
+    const NAME: &str = "org.twardyece.Manager1";
+    const PATH: &str = "/org/twardyece/Manager1";
+
+    /// Lets polkit ask the user to authenticate, e.g. through a desktop
+    /// agent, when an action needs it.
+    const ALLOW_USER_INTERACTION: u32 = 1;
+
+    #[dbus_proxy(
+        interface = "org.freedesktop.PolicyKit1.Authority",
+        default_service = "org.freedesktop.PolicyKit1",
+        default_path = "/org/freedesktop/PolicyKit1/Authority"
+    )]
+    trait Authority {
+        fn check_authorization(
+            &self,
+            subject: &(&str, HashMap<&str, Value<'_>>),
+            action_id: &str,
+            details: HashMap<&str, &str>,
+            flags: u32,
+            cancellation_id: &str,
+        ) -> zbus::Result<(bool, bool, HashMap<String, String>)>;
+    }
+
+    /// Checks with polkit that the sender of the message may perform
+    /// `action`.
+    async fn authorize(
+        connection: &Connection,
+        header: &MessageHeader<'_>,
+        action: &str,
+    ) -> fdo::Result<()> {
+        let sender = header
+            .sender()?
+            .ok_or_else(|| fdo::Error::AccessDenied("message has no sender".to_string()))?;
+        let subject = (
+            "system-bus-name",
+            HashMap::from([("name", Value::from(sender.as_str()))]),
+        );
+        let (authorized, _, _) = AuthorityProxy::new(connection)
+            .await?
+            .check_authorization(&subject, action, HashMap::new(), ALLOW_USER_INTERACTION, "")
+            .await?;
+        if !authorized {
+            return Err(fdo::Error::AccessDenied(format!(
+                "{} is not authorized for {}",
+                sender, action
+            )));
+        }
+        Ok(())
+    }
+
+    fn name<T: serde::Serialize>(value: &T) -> String {
+        serde_json::to_value(value)
+            .ok()
+            .and_then(|value| value.as_str().map(String::from))
+            .unwrap_or_default()
+    }
+
+    struct Manager {
+        members: Members,
+        events: Events,
+        restrictions: Restrictions,
+    }
+
+    #[dbus_interface(name = "org.twardyece.Manager1")]
+    impl Manager {
+        /// The id, name and power state of every system.
+        async fn list_systems(
+            &self,
+            #[zbus(connection)] connection: &Connection,
+            #[zbus(header)] header: MessageHeader<'_>,
+        ) -> fdo::Result<Vec<(String, String, String)>> {
+            authorize(connection, &header, "org.twardyece.manager.list").await?;
+            Ok(self
+                .members
+                .list()
+                .into_iter()
+                .filter(|system| !system.absent)
+                .map(|system| (system.id.0, system.name.0, name(&system.power_state)))
+                .collect())
+        }
+
+        /// Resets the system `id`, where `reset_type` is a Redfish ResetType
+        /// such as ForceOff.
+        async fn reset(
+            &self,
+            #[zbus(connection)] connection: &Connection,
+            #[zbus(header)] header: MessageHeader<'_>,
+            id: String,
+            reset_type: String,
+        ) -> fdo::Result<()> {
+            authorize(connection, &header, "org.twardyece.manager.reset").await?;
+            if self.restrictions.read_only {
+                return Err(fdo::Error::AccessDenied(
+                    "the service is read-only".to_string(),
+                ));
+            }
+            if self.restrictions.approval_reset_types.contains(&reset_type) {
+                return Err(fdo::Error::AccessDenied(format!(
+                    "{} needs a second user's approval, which is only possible over Redfish",
+                    reset_type
+                )));
+            }
+            if !self.members.contains(&id) {
+                return Err(fdo::Error::InvalidArgs(format!("no system {}", id)));
+            }
+            let body: ResetRequestBody =
+                serde_json::from_value(serde_json::json!({ "ResetType": reset_type }))
+                    .map_err(|_| fdo::Error::InvalidArgs(format!("no ResetType {}", reset_type)))?;
+            tracing::info!("D-Bus reset of system {} by {:?}", id, header.sender());
+            self.members
+                .reset(&id, body, &self.events)
+                .map_err(|message| {
+                    let message: redfish_codegen::models::redfish::Message = message.into();
+                    fdo::Error::Failed(serde_json::to_string(&message).unwrap_or_default())
+                })
+        }
+    }
+
+    async fn serve(
+        members: Members,
+        events: Events,
+        restrictions: Restrictions,
+    ) -> anyhow::Result<()> {
+        let manager = Manager {
+            members,
+            events,
+            restrictions,
+        };
+        let _connection = ConnectionBuilder::system()?
+            .name(NAME)?
+            .serve_at(PATH, manager)?
+            .build()
+            .await?;
+        tracing::info!("serving {} on the system bus", NAME);
+        std::future::pending::<()>().await;
+        Ok(())
+    }
+
+    pub fn start(
+        supervisor: &mut Supervisor,
+        members: Members,
+        events: Events,
+        restrictions: Restrictions,
+    ) {
+        supervisor.spawn(
+            "dbus",
+            Restart {
+                max: 3,
+                backoff: Duration::from_secs(1),
+            },
+            false,
+            move || serve(members.clone(), events.clone(), restrictions.clone()),
+        );
+    }
+}
+
+/// Serves org.twardyece.Manager1 on the system bus, so that tooling on the
+/// host can list and reset the systems in `members` without HTTP
+/// credentials. Every call is authorized by polkit, with the actions in
+/// dbus/org.twardyece.manager.policy, and the bus policy in
+/// dbus/org.twardyece.Manager1.conf must be installed to claim the name.
+/// Resets are refused when `restrictions` would hold or reject them over
+/// HTTP.
+#[cfg(feature = "dbus")]
+pub fn start(
+    supervisor: &mut Supervisor,
+    members: Members,
+    events: Events,
+    restrictions: Restrictions,
+) -> anyhow::Result<()> {
+    service::start(supervisor, members, events, restrictions);
+    Ok(())
+}
+
+#[cfg(not(feature = "dbus"))]
+pub fn start(
+    supervisor: &mut Supervisor,
+    members: Members,
+    events: Events,
+    restrictions: Restrictions,
+) -> anyhow::Result<()> {
+    let _ = (supervisor, members, events, restrictions);
+    anyhow::bail!("dbus is configured, but this build does not support it")
+}
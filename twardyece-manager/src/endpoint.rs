@@ -14,6 +14,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod accounts;
+pub use accounts::*;
+
 mod service_root;
 pub use service_root::*;
 
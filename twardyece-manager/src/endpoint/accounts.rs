@@ -0,0 +1,167 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::Body,
+    extract::Path,
+    http::{request::Parts, Request, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, MethodRouter},
+    Json,
+};
+use redfish_codegen::registries::base::v1_15_0::Base;
+use serde_json::json;
+use seuss::{
+    auth::{AuthenticateRequest, AuthenticatedUser, Role},
+    redfish_error,
+};
+
+use crate::{
+    auth::{Authenticator, CachingAuthenticator, PasswordFileAuthenticator},
+    links::{self, LinkBuilder},
+};
+
+fn error(status: StatusCode, message: Base) -> Response {
+    (status, Json(redfish_error::one_message(message.into()))).into_response()
+}
+
+/// The accounts of a password file, which their users may read and change
+/// the password of. Administrators may do so for every account.
+#[derive(Clone)]
+pub struct Accounts<A> {
+    accounts: PasswordFileAuthenticator,
+    /// Forgets the old password once it is changed.
+    cache: CachingAuthenticator<Authenticator>,
+    authenticator: A,
+    link: LinkBuilder,
+}
+
+impl<A> Accounts<A>
+where
+    A: AuthenticateRequest + Clone + Send + Sync + 'static,
+{
+    pub fn new(
+        accounts: PasswordFileAuthenticator,
+        cache: CachingAuthenticator<Authenticator>,
+        authenticator: A,
+        link: LinkBuilder,
+    ) -> Self {
+        Accounts {
+            accounts,
+            cache,
+            authenticator,
+            link,
+        }
+    }
+
+    /// Resolves the user of `parts`, if it may access the account `name`.
+    fn authorize(
+        &self,
+        parts: &mut Parts,
+        name: &str,
+    ) -> Result<AuthenticatedUser, (StatusCode, Base)> {
+        let user = match self.authenticator.authenticate_request(parts) {
+            Ok(Some(user)) => user,
+            Ok(None) | Err(_) => return Err((StatusCode::UNAUTHORIZED, Base::NoValidSession)),
+        };
+        if user.username != name && user.role != Role::Administrator {
+            return Err((StatusCode::FORBIDDEN, Base::InsufficientPrivilege));
+        }
+        if !self.accounts.exists(name) {
+            let message = Base::ResourceNotFound("ManagerAccount".to_string(), name.to_string());
+            return Err((StatusCode::NOT_FOUND, message));
+        }
+        Ok(user)
+    }
+
+    fn account(&self, name: &str) -> serde_json::Value {
+        json!({
+            "@odata.id": links::member(&self.link.id(links::ACCOUNTS), name).0,
+            "@odata.type": "#ManagerAccount.v1_10_0.ManagerAccount",
+            "Id": name,
+            "Name": "User Account",
+            "UserName": name,
+            "PasswordChangeRequired": self.accounts.password_change_required(name),
+        })
+    }
+
+    async fn get(self, name: String, mut parts: Parts) -> Response {
+        if let Err((status, message)) = self.authorize(&mut parts, &name) {
+            return error(status, message);
+        }
+        Json(self.account(&name)).into_response()
+    }
+
+    /// Only the Password can be changed.
+    async fn patch(self, name: String, request: Request<Body>) -> Response {
+        let (mut parts, body) = request.into_parts();
+        let user = match self.authorize(&mut parts, &name) {
+            Ok(user) => user,
+            Err((status, message)) => return error(status, message),
+        };
+        let body = match hyper::body::to_bytes(body)
+            .await
+            .map(|bytes| serde_json::from_slice(&bytes))
+        {
+            Ok(Ok(serde_json::Value::Object(body))) => body,
+            _ => return error(StatusCode::BAD_REQUEST, Base::MalformedJSON),
+        };
+        if let Some(property) = body.keys().find(|property| *property != "Password") {
+            let message = Base::PropertyNotWritable(property.clone());
+            return error(StatusCode::BAD_REQUEST, message);
+        }
+        let password = match body.get("Password") {
+            Some(serde_json::Value::String(password)) if !password.is_empty() => password.clone(),
+            Some(value) => {
+                let message =
+                    Base::PropertyValueIncorrect("Password".to_string(), value.to_string());
+                return error(StatusCode::BAD_REQUEST, message);
+            }
+            None => return Json(self.account(&name)).into_response(),
+        };
+
+        let (accounts, account) = (self.accounts.clone(), name.clone());
+        let result =
+            tokio::task::spawn_blocking(move || accounts.change_password(&account, &password))
+                .await;
+        match result {
+            Ok(Ok(())) => {
+                self.cache.forget(&name);
+                tracing::info!("password of {} changed by {}", name, user.username);
+                Json(self.account(&name)).into_response()
+            }
+            Ok(Err(failure)) => {
+                tracing::error!("failed to change the password of {}: {:#}", name, failure);
+                error(StatusCode::INTERNAL_SERVER_ERROR, Base::InternalError)
+            }
+            Err(_) => error(StatusCode::INTERNAL_SERVER_ERROR, Base::InternalError),
+        }
+    }
+}
+
+impl<A> From<Accounts<A>> for MethodRouter
+where
+    A: AuthenticateRequest + Clone + Send + Sync + 'static,
+{
+    fn from(accounts: Accounts<A>) -> Self {
+        let patch_accounts = accounts.clone();
+        get(move |Path(name): Path<String>, parts: Parts| accounts.clone().get(name, parts)).patch(
+            move |Path(name): Path<String>, request: Request<Body>| {
+                patch_accounts.clone().patch(name, request)
+            },
+        )
+    }
+}
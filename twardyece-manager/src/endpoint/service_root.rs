@@ -15,7 +15,7 @@
 // limitations under the License.
 
 use redfish_codegen::api::v1;
-use redfish_codegen::models::service_root::v1_15_0::Links;
+use redfish_codegen::models::service_root::v1_15_0::{Links, ProtocolFeaturesSupported};
 use redfish_codegen::models::{odata_v4, resource, service_root};
 
 #[derive(Clone, Default)]
@@ -26,34 +26,47 @@ pub struct ServiceRoot {
     systems: Option<odata_v4::IdRef>,
     session_service: Option<odata_v4::IdRef>,
     sessions_link: odata_v4::IdRef,
+    protocol_features_supported: Option<ProtocolFeaturesSupported>,
 }
 
 impl ServiceRoot {
-    pub fn new(name: resource::Name, id: resource::Id) -> Self {
+    pub fn new(odata_id: odata_v4::Id, name: resource::Name, id: resource::Id) -> Self {
         Self {
             name,
             id,
-            odata_id: odata_v4::Id(String::default()),
+            odata_id,
             ..Default::default()
         }
     }
 
-    pub fn enable_systems(mut self) -> Self {
+    pub fn enable_systems(mut self, systems_id: odata_v4::Id) -> Self {
         self.systems = Some(odata_v4::IdRef {
-            odata_id: Some(odata_v4::Id("/redfish/v1/Systems".to_string())),
+            odata_id: Some(systems_id),
         });
         self
     }
 
-    pub fn enable_sessions(mut self, session_collection_id: odata_v4::Id) -> Self {
+    pub fn enable_sessions(
+        mut self,
+        session_service_id: odata_v4::Id,
+        session_collection_id: odata_v4::Id,
+    ) -> Self {
         self.session_service = Some(odata_v4::IdRef {
-            odata_id: Some(odata_v4::Id("/redfish/v1/SessionService".to_string())),
+            odata_id: Some(session_service_id),
         });
         self.sessions_link = odata_v4::IdRef {
             odata_id: Some(session_collection_id),
         };
         self
     }
+
+    pub fn enable_only_member_query(mut self) -> Self {
+        self.protocol_features_supported = Some(ProtocolFeaturesSupported {
+            only_member_query: Some(true),
+            ..Default::default()
+        });
+        self
+    }
 }
 
 impl v1::ServiceRoot for ServiceRoot {
@@ -65,6 +78,7 @@ impl v1::ServiceRoot for ServiceRoot {
             systems,
             session_service,
             sessions_link,
+            protocol_features_supported,
         } = self.clone();
         v1::ServiceRootGetResponse::Ok(service_root::v1_15_0::ServiceRoot {
             name,
@@ -76,7 +90,50 @@ impl v1::ServiceRoot for ServiceRoot {
                 sessions: sessions_link,
                 ..Default::default()
             },
+            protocol_features_supported,
             ..Default::default()
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{golden, links, middleware};
+    use axum::{
+        body::Body,
+        http::Request,
+        middleware::{from_fn, Next},
+        Router,
+    };
+    use seuss::routing;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn service_root() {
+        let link = links::LinkBuilder::new("");
+        let service_root = ServiceRoot::new(
+            odata_v4::Id("/redfish/v1".to_string()),
+            resource::Name("Basic Redfish Service".to_string()),
+            resource::Id("example-basic".to_string()),
+        )
+        .enable_systems(link.id(links::SYSTEMS))
+        .enable_sessions(link.id(links::SESSION_SERVICE), link.id(links::SESSIONS))
+        .enable_only_member_query();
+        let app = Router::new().route(
+            links::SERVICE_ROOT,
+            routing::ServiceRoot::new(service_root).into(),
+        );
+        let etags = middleware::ETags::new(Default::default());
+        let app = app
+            .clone()
+            .layer(from_fn(move |request: Request<Body>, next: Next<Body>| {
+                middleware::etags(app.clone(), etags.clone(), request, next)
+            }));
+
+        let request = Request::get(links::SERVICE_ROOT)
+            .body(Body::empty())
+            .unwrap();
+        golden::assert_golden("service_root", app.oneshot(request).await.unwrap()).await;
+    }
+}
@@ -14,6 +14,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use axum::{
+    extract::Path,
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{post, MethodRouter},
+    Json,
+};
 use redfish_codegen::api::v1::{computer_system_detail, systems};
 use redfish_codegen::models::{
     computer_system::v1_20_0::{Actions, ComputerSystem, Reset, ResetRequestBody},
@@ -21,32 +28,67 @@ use redfish_codegen::models::{
     odata_v4, resource,
 };
 use redfish_codegen::registries::base::v1_15_0::Base;
-use seuss::{auth::AuthenticateRequest, redfish_error};
-use std::sync::{Arc, Mutex};
+use seuss::{
+    auth::{AuthenticateRequest, Role},
+    redfish_error,
+};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    action,
+    events::{Event, Events},
+    links, power,
+};
 
 #[derive(Clone, Default)]
 pub struct DummySystem {
     pub odata_id: odata_v4::Id,
+    pub id: resource::Id,
     pub name: resource::Name,
     pub power_state: resource::PowerState,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    /// Set when the system has been deleted. Deleted systems stay in the
+    /// collection with Status.State=Absent until they are restored.
+    pub absent: bool,
 }
 
 impl Into<ComputerSystem> for DummySystem {
     fn into(self) -> ComputerSystem {
         let DummySystem {
             name,
+            id,
             odata_id,
             power_state,
+            manufacturer,
+            model,
+            serial_number,
+            absent,
         } = self;
-        let id = resource::Id(name.0.clone());
+        let state = if absent {
+            resource::State::Absent
+        } else {
+            resource::State::Enabled
+        };
         ComputerSystem {
             odata_id: odata_id.clone(),
             name,
             id,
             power_state: Some(power_state),
+            manufacturer,
+            model,
+            serial_number,
+            status: Some(resource::Status {
+                state: Some(state),
+                ..Default::default()
+            }),
             actions: Some(Actions {
                 computer_system_reset: Some(Reset {
-                    target: Some(odata_id.0 + "/Actions/ComputerSystem.Reset"),
+                    target: Some(links::action(&odata_id.0, "ComputerSystem.Reset")),
                     ..Default::default()
                 }),
                 ..Default::default()
@@ -63,8 +105,11 @@ where
 {
     odata_id: odata_v4::Id,
     systems: Arc<Mutex<Vec<DummySystem>>>,
+    created: Arc<Mutex<HashSet<String>>>,
     name: resource::Name,
     auth_handler: S,
+    allow_create: bool,
+    events: Events,
 }
 
 impl<S> Systems<S>
@@ -80,8 +125,114 @@ where
         Systems {
             odata_id,
             systems: Arc::new(Mutex::new(systems)),
+            created: Arc::new(Mutex::new(HashSet::new())),
             name,
             auth_handler,
+            allow_create: false,
+            events: Events::default(),
+        }
+    }
+
+    /// Allow PUT on an id that doesn't exist yet to create the system.
+    pub fn allow_create(mut self, allow_create: bool) -> Self {
+        self.allow_create = allow_create;
+        self
+    }
+
+    /// Publish changes to the systems, such as power transitions, to `events`.
+    pub fn publish_to(mut self, events: Events) -> Self {
+        self.events = events;
+        self
+    }
+
+    pub fn members(&self) -> Members {
+        Members {
+            systems: self.systems.clone(),
+            created: self.created.clone(),
+        }
+    }
+}
+
+/// Shared view of the systems in a collection, for code outside the
+/// endpoint that needs to know which systems exist.
+#[derive(Clone)]
+pub struct Members {
+    systems: Arc<Mutex<Vec<DummySystem>>>,
+    created: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Members {
+    pub fn contains(&self, id: &str) -> bool {
+        self.systems.lock().unwrap().iter().any(|system| id == system.id.0)
+    }
+
+    pub fn list(&self) -> Vec<DummySystem> {
+        self.systems.lock().unwrap().clone()
+    }
+
+    /// Whether a PUT created the system `id`, returning the @odata.id of the
+    /// new system. Each creation is reported once, so that only one of
+    /// several concurrent PUTs for the same id answers that it created it.
+    pub fn take_created(&self, id: &str) -> Option<odata_v4::Id> {
+        if !self.created.lock().unwrap().remove(id) {
+            return None;
+        }
+        self.systems
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|system| id == system.id.0)
+            .map(|system| system.odata_id.clone())
+    }
+
+    /// Brings back the system `id`, if it was deleted.
+    pub fn restore(&self, id: &str) -> Result<(), Base> {
+        match self
+            .systems
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|system| id == system.id.0)
+        {
+            Some(system) => {
+                system.absent = false;
+                Ok(())
+            }
+            None => Err(Base::ResourceNotFound(
+                "ComputerSystem".to_string(),
+                id.to_string(),
+            )),
+        }
+    }
+
+    /// Resets the system `id`, publishing any change of its power state to
+    /// `events`.
+    pub fn reset(&self, id: &str, body: ResetRequestBody, events: &Events) -> Result<(), Base> {
+        match self
+            .systems
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|system| id == system.id.0)
+        {
+            Some(system) if system.absent => {
+                Err(Base::ActionNotSupported("ComputerSystem.Reset".to_string()))
+            }
+            Some(system) => {
+                let previous = std::mem::discriminant(&system.power_state);
+                let result = reset(system, body);
+                if std::mem::discriminant(&system.power_state) != previous {
+                    events.publish(Event::PowerStateChanged {
+                        system: id.to_string(),
+                        power_state: system.power_state.clone(),
+                    });
+                }
+                result
+            }
+            None => Err(Base::ActionParameterMissing(
+                "ComputerSystem.Reset".to_string(),
+                "ResetType".to_string(),
+            )),
         }
     }
 }
@@ -132,7 +283,7 @@ where
             .lock()
             .unwrap()
             .iter()
-            .find(|system| id == system.name.0)
+            .find(|system| id == system.id.0)
         {
             Some(system) => {
                 computer_system_detail::ComputerSystemDetailGetResponse::Ok(system.clone().into())
@@ -145,28 +296,120 @@ where
 
     fn put(
         &mut self,
-        _id: String,
-        _body: ComputerSystem,
+        id: String,
+        body: ComputerSystem,
     ) -> computer_system_detail::ComputerSystemDetailPutResponse {
-        todo!()
+        use computer_system_detail::ComputerSystemDetailPutResponse;
+        // An Id in the body other than the one in the URI is handled by the
+        // identifiers middleware, according to the configured policy.
+        let mut systems = self.systems.lock().unwrap();
+        let index = match systems.iter().position(|system| id == system.id.0) {
+            Some(index) => index,
+            None if self.allow_create => {
+                // Decided here, under the lock, so that concurrent PUTs
+                // can't both be answered with 201 Created.
+                self.created.lock().unwrap().insert(id.clone());
+                systems.push(DummySystem {
+                    odata_id: links::member(&self.odata_id, &id),
+                    id: resource::Id(id.clone()),
+                    name: resource::Name(id),
+                    power_state: resource::PowerState::Off,
+                    ..Default::default()
+                });
+                systems.len() - 1
+            }
+            None => {
+                return ComputerSystemDetailPutResponse::Default(redfish_error::one_message(
+                    Base::ResourceNotFound("ComputerSystem".to_string(), id).into(),
+                ))
+            }
+        };
+
+        // Only the descriptive properties can be replaced. Power state is
+        // changed through the Reset action.
+        let system = &mut systems[index];
+        if !body.name.0.is_empty() {
+            system.name = body.name;
+        }
+        system.manufacturer = body.manufacturer;
+        system.model = body.model;
+        system.serial_number = body.serial_number;
+        ComputerSystemDetailPutResponse::Ok(system.clone().into())
     }
 
     fn delete(
         &mut self,
-        _id: String,
+        id: String,
     ) -> computer_system_detail::ComputerSystemDetailDeleteResponse {
-        todo!()
+        use computer_system_detail::ComputerSystemDetailDeleteResponse;
+        match self
+            .systems
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|system| id == system.id.0)
+        {
+            Some(system) => {
+                system.absent = true;
+                ComputerSystemDetailDeleteResponse::NoContent
+            }
+            None => ComputerSystemDetailDeleteResponse::Default(redfish_error::one_message(
+                Base::ResourceNotFound("ComputerSystem".to_string(), id).into(),
+            )),
+        }
     }
 
     fn patch(
         &mut self,
-        _id: String,
-        _body: serde_json::Value,
+        id: String,
+        body: serde_json::Value,
     ) -> computer_system_detail::ComputerSystemDetailPatchResponse {
-        todo!()
+        use computer_system_detail::ComputerSystemDetailPatchResponse;
+        let systems = self.systems.lock().unwrap();
+        let system = match systems.iter().find(|system| id == system.id.0) {
+            Some(system) => system,
+            None => {
+                return ComputerSystemDetailPatchResponse::Default(redfish_error::one_message(
+                    Base::ResourceNotFound("ComputerSystem".to_string(), id).into(),
+                ))
+            }
+        };
+
+        // None of the properties can be modified with PATCH. Deleted systems
+        // are brought back with the TwardyEce.Restore action instead.
+        match first_property(&body) {
+            Some(property) => ComputerSystemDetailPatchResponse::Default(
+                redfish_error::one_message(Base::PropertyNotWritable(property).into()),
+            ),
+            None => ComputerSystemDetailPatchResponse::Ok(system.clone().into()),
+        }
+    }
+}
+
+/// The path, e.g. Status/State, of the first property set in `body`.
+fn first_property(body: &serde_json::Value) -> Option<String> {
+    let (name, value) = body.as_object()?.iter().next()?;
+    match first_property(value) {
+        Some(property) => Some(name.clone() + "/" + &property),
+        None => Some(name.clone()),
     }
 }
 
+fn reset(system: &mut DummySystem, body: ResetRequestBody) -> Result<(), Base> {
+    let reset_type = match body.reset_type {
+        Some(reset_type) => reset_type,
+        None => {
+            return Err(Base::ActionParameterMissing(
+                "Reset".to_string(),
+                "ResetType".to_string(),
+            ))
+        }
+    };
+
+    system.power_state = power::reset(&system.power_state, reset_type)?;
+    Ok(())
+}
+
 impl<S> computer_system_detail::reset::Reset for Systems<S>
 where
     S: AuthenticateRequest + Clone,
@@ -178,61 +421,57 @@ where
         body: ResetRequestBody,
     ) -> computer_system_detail::reset::ResetPostResponse {
         use computer_system_detail::reset::ResetPostResponse;
-        use resource::ResetType::*;
-        match self
-            .systems
-            .lock()
-            .unwrap()
-            .iter_mut()
-            .find(|system| id == system.name.0)
-        {
-            Some(system) => {
-                if body.reset_type.is_none() {
-                    let message =
-                        Base::ActionParameterMissing("Reset".to_string(), "ResetType".to_string());
-                    return ResetPostResponse::Default(redfish_error::one_message(message.into()));
-                }
-                let reset_type = body.reset_type.unwrap();
-
-                match reset_type {
-                    GracefulRestart | ForceRestart | On | ForceOn | PowerCycle => {
-                        system.power_state = resource::PowerState::On;
-                        ResetPostResponse::Ok(redfish_error::one_message(Base::Success.into()))
-                    }
-                    ForceOff | GracefulShutdown => {
-                        system.power_state = resource::PowerState::Off;
-                        ResetPostResponse::Ok(redfish_error::one_message(Base::Success.into()))
-                    }
-                    Nmi | Suspend | Pause | Resume => {
-                        ResetPostResponse::Default(redfish_error::one_message(
-                            Base::PropertyNotUpdated("PowerState".to_string()).into(),
-                        ))
-                    }
-                    PushPowerButton => {
-                        match system.power_state {
-                            resource::PowerState::On | resource::PowerState::PoweringOn => {
-                                system.power_state = resource::PowerState::Off
-                            }
-                            resource::PowerState::Off | resource::PowerState::PoweringOff => {
-                                system.power_state = resource::PowerState::On
-                            }
-                            resource::PowerState::Paused => {
-                                return ResetPostResponse::Default(redfish_error::one_message(
-                                    Base::PropertyValueError("PowerState".to_string()).into(),
-                                ))
-                            }
-                        };
-                        ResetPostResponse::Ok(redfish_error::one_message(Base::Success.into()))
-                    }
-                }
+        match self.members().reset(&id, body, &self.events) {
+            Ok(()) => ResetPostResponse::Ok(redfish_error::one_message(Base::Success.into())),
+            Err(message) => ResetPostResponse::Default(redfish_error::one_message(message.into())),
+        }
+    }
+}
+
+/// The actions of a system, completed in its representation by
+/// [action::advertise].
+pub const ACTIONS: &[action::Action] = &[
+    action::Action {
+        name: "ComputerSystem.Reset",
+        oem: false,
+        info: Some(&power::RESET_ACTION_INFO),
+    },
+    action::Action {
+        name: "TwardyEce.Restore",
+        oem: true,
+        info: None,
+    },
+];
+
+fn error(status: StatusCode, message: Base) -> Response {
+    (status, Json(redfish_error::one_message(message.into()))).into_response()
+}
+
+/// Routes the TwardyEce.Restore action of the systems in `members`, which
+/// brings back a deleted system. seuss doesn't route OEM actions, so the user
+/// is resolved with `authenticator` here. Restoring a system takes the
+/// ConfigureComponents privilege, like deleting it.
+pub fn restore<A>(members: Members, authenticator: A) -> MethodRouter
+where
+    A: AuthenticateRequest + Clone + Send + Sync + 'static,
+{
+    action::target(post(move |Path(id): Path<String>, mut parts: Parts| {
+        let (members, authenticator) = (members.clone(), authenticator.clone());
+        async move {
+            let user = match authenticator.authenticate_request(&mut parts) {
+                Ok(Some(user)) => user,
+                Ok(None) | Err(_) => return error(StatusCode::UNAUTHORIZED, Base::NoValidSession),
+            };
+            if user.role == Role::ReadOnly {
+                return error(StatusCode::FORBIDDEN, Base::InsufficientPrivilege);
             }
-            None => {
-                let message = Base::ActionParameterMissing(
-                    "ComputerSystem.Reset".to_string(),
-                    "ResetType".to_string(),
-                );
-                ResetPostResponse::Default(redfish_error::one_message(message.into()))
+            match members.restore(&id) {
+                Ok(()) => {
+                    tracing::info!("system {} restored by {}", id, user.username);
+                    Json(redfish_error::one_message(Base::Success.into())).into_response()
+                }
+                Err(message) => error(StatusCode::NOT_FOUND, message),
             }
         }
-    }
+    }))
 }
@@ -0,0 +1,143 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use serde_yaml::{Mapping, Value};
+use std::{fs::File, path::Path};
+
+use crate::migration;
+
+/// Prefix of environment variables that set configuration values.
+const PREFIX: &str = "TWARDYECE_MANAGER_";
+
+/// Variables with the prefix that are command line options, not
+/// configuration values.
+const OPTIONS: &[&str] = &["CONFIG", "DATA_DIR", "LOG_FORMAT"];
+
+/// Defaults for a deployment where all state lives under one directory, e.g.
+/// a volume mounted at /data in a container.
+fn defaults(data_dir: &Path) -> Value {
+    let path = |name: &str| Value::String(data_dir.join(name).to_string_lossy().into_owned());
+    let mut server = Mapping::new();
+    server.insert("address".into(), "0.0.0.0".into());
+    let mut ports = Mapping::new();
+    ports.insert("http".into(), 8080.into());
+    ports.insert("https".into(), 8443.into());
+    server.insert("ports".into(), ports.into());
+    server.insert("certificate-file".into(), path("tls/cert.pem"));
+    server.insert("key-file".into(), path("tls/key.pem"));
+
+    let mut backend = Mapping::new();
+    backend.insert("type".into(), "password-file".into());
+    backend.insert("path".into(), path("passwd"));
+    let mut authentication = Mapping::new();
+    authentication.insert("backend".into(), backend.into());
+
+    let mut config = Mapping::new();
+    config.insert("server".into(), server.into());
+    config.insert("authentication".into(), authentication.into());
+    config.into()
+}
+
+/// Recursively merges `overlay` into `base`. Mappings are merged key by key;
+/// any other value in `overlay` replaces the one in `base`.
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// The key of `mapping` named by the variable path component `component`.
+/// Keys already in the configuration are matched regardless of case and of
+/// '-' or '_', so that e.g. `ROLE_MAP__ADMINISTRATOR` finds an existing
+/// `Administrator` key. Other keys are taken to be kebab-case.
+fn key(mapping: &Mapping, component: &str) -> Value {
+    let component = component.to_uppercase();
+    mapping
+        .keys()
+        .find(|key| {
+            key.as_str()
+                .is_some_and(|key| key.to_uppercase().replace('-', "_") == component)
+        })
+        .cloned()
+        .unwrap_or_else(|| component.to_lowercase().replace('_', "-").into())
+}
+
+fn set(config: &mut Value, path: &[&str], value: Value) {
+    let (component, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return merge(config, value),
+    };
+    if !config.is_mapping() {
+        *config = Value::Mapping(Mapping::new());
+    }
+    if let Value::Mapping(mapping) = config {
+        let key = key(mapping, component);
+        set(mapping.entry(key).or_insert(Value::Null), rest, value);
+    }
+}
+
+/// Applies e.g. `TWARDYECE_MANAGER_SERVER__PORTS__HTTPS=3001` to `config` as
+/// `server: { ports: { https: 3001 } }`. Path components are separated by a
+/// double underscore, and are matched to keys as described for [`key`].
+/// Values are parsed as YAML, so numbers, booleans and flow sequences such
+/// as `[0.0.0.0, "::"]` work as expected, and a flow mapping such as
+/// `{Administrator: admins}` can add keys that aren't kebab-case.
+fn apply_variables(config: &mut Value, variables: impl Iterator<Item = (String, String)>) {
+    for (name, value) in variables {
+        let key = match name.strip_prefix(PREFIX) {
+            Some(key) if !OPTIONS.contains(&key) => key,
+            _ => continue,
+        };
+
+        let value = serde_yaml::from_str(&value).unwrap_or(Value::String(value));
+        set(config, &key.split("__").collect::<Vec<_>>(), value);
+    }
+}
+
+/// Loads the configuration from, in increasing order of precedence, the
+/// defaults for `data_dir`, the configuration file, and the environment.
+/// Configuration files written for older versions are migrated first.
+pub fn load<T: serde::de::DeserializeOwned>(
+    file: Option<&Path>,
+    data_dir: Option<&Path>,
+) -> anyhow::Result<T> {
+    let mut config = match data_dir {
+        Some(data_dir) => defaults(data_dir),
+        None => Value::Mapping(Mapping::new()),
+    };
+    if let Some(path) = file {
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        let mut file = serde_yaml::from_reader(file)?;
+        for summary in migration::migrate(&mut file)? {
+            tracing::warn!("{}: {}", path.display(), summary);
+        }
+        merge(&mut config, file);
+    }
+    apply_variables(&mut config, std::env::vars());
+    Ok(serde_yaml::from_value(config)?)
+}
@@ -0,0 +1,99 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use redfish_codegen::models::resource;
+use tokio::sync::broadcast;
+
+/// Events are dropped for subscribers that fall this far behind.
+const CAPACITY: usize = 64;
+
+/// State transitions inside the service, for integrations such as webhooks.
+/// These are independent of the Redfish EventService.
+#[derive(Clone)]
+pub enum Event {
+    PowerStateChanged {
+        system: String,
+        power_state: resource::PowerState,
+    },
+}
+
+impl Event {
+    /// Name of the kind of event, used to select events in the configuration.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::PowerStateChanged { .. } => "power-state-changed",
+        }
+    }
+
+    /// Properties of the event, by name, including its kind as `event`.
+    fn properties(&self) -> Vec<(&'static str, String)> {
+        let mut properties = vec![("event", self.kind().to_string())];
+        match self {
+            Event::PowerStateChanged {
+                system,
+                power_state,
+            } => {
+                let power_state = serde_json::to_value(power_state)
+                    .ok()
+                    .and_then(|power_state| power_state.as_str().map(String::from))
+                    .unwrap_or_default();
+                properties.push(("system", system.clone()));
+                properties.push(("power-state", power_state));
+            }
+        }
+        properties
+    }
+
+    /// Replaces every occurrence of `{name}` in `template` by the property
+    /// `name` of the event, e.g. `{system}`.
+    pub fn format(&self, template: &str) -> String {
+        self.properties()
+            .iter()
+            .fold(template.to_string(), |formatted, (name, value)| {
+                formatted.replace(&format!("{{{}}}", name), value)
+            })
+    }
+
+    /// An object holding every property of the event.
+    pub fn to_json(&self) -> serde_json::Value {
+        self.properties()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), serde_json::Value::String(value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+/// Publishes events to every subscriber. Events published while nobody is
+/// subscribed are dropped.
+#[derive(Clone)]
+pub struct Events(broadcast::Sender<Event>);
+
+impl Default for Events {
+    fn default() -> Self {
+        Events(broadcast::channel(CAPACITY).0)
+    }
+}
+
+impl Events {
+    pub fn publish(&self, event: Event) {
+        let _ = self.0.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.0.subscribe()
+    }
+}
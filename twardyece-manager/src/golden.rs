@@ -0,0 +1,184 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{http::header, response::Response};
+use serde_json::{json, Map, Value};
+use std::path::PathBuf;
+
+const ETAG: &str = "<etag>";
+const TIMESTAMP: &str = "<timestamp>";
+
+/// Set to rewrite the golden files from the responses, rather than compare.
+const UPDATE: &str = "UPDATE_GOLDEN";
+
+fn path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("golden")
+        .join(name.to_string() + ".json")
+}
+
+/// The properties of each schema that it declares with `"format":
+/// "date-time"`, whose values depend on when the test ran. A property is
+/// matched in the objects whose @odata.type names the schema, and in the
+/// objects nested in them that name no schema of their own.
+const DATE_TIMES: &[(&str, &[&str])] = &[
+    ("ComputerSystem", &["LastResetTime"]),
+    ("Event", &["EventTimestamp"]),
+    ("LogEntry", &["Created", "Modified"]),
+    ("Manager", &["DateTime", "LastResetTime"]),
+    ("Session", &["CreatedTime"]),
+    ("Task", &["StartTime", "EndTime"]),
+];
+
+/// The schema named by the @odata.type of `object`, e.g. Session for
+/// `#Session.v1_6_0.Session`.
+fn schema(object: &Map<String, Value>) -> Option<String> {
+    let odata_type = object.get("@odata.type")?.as_str()?;
+    let schema = odata_type.trim_start_matches('#').split('.').next()?;
+    Some(schema.to_string())
+}
+
+fn date_times(schema: Option<&str>) -> &'static [&'static str] {
+    DATE_TIMES
+        .iter()
+        .find(|(name, _)| Some(*name) == schema)
+        .map(|(_, properties)| *properties)
+        .unwrap_or_default()
+}
+
+fn normalize_as(value: &mut Value, schema: Option<&str>) {
+    match value {
+        Value::Object(object) => {
+            let own = self::schema(object);
+            let schema = own.as_deref().or(schema);
+            let date_times = date_times(schema);
+            for (key, value) in object.iter_mut() {
+                if key == "@odata.etag" {
+                    *value = Value::String(ETAG.to_string());
+                } else if date_times.contains(&key.as_str()) && value.is_string() {
+                    *value = Value::String(TIMESTAMP.to_string());
+                } else {
+                    normalize_as(value, schema);
+                }
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| normalize_as(value, schema)),
+        _ => {}
+    }
+}
+
+/// Replaces the values that change from run to run with placeholders: the
+/// @odata.etag annotation, and the date-time properties of the schema of
+/// each resource.
+pub fn normalize(value: &mut Value) {
+    normalize_as(value, None)
+}
+
+/// The parts of `response` that are compared: the status, whether it carries
+/// an ETag, and the body, normalized.
+pub async fn snapshot(response: Response) -> Value {
+    let (parts, body) = response.into_parts();
+    let bytes = hyper::body::to_bytes(body).await.unwrap();
+    let mut snapshot = Map::new();
+    snapshot.insert("Status".to_string(), json!(parts.status.as_u16()));
+    if parts.headers.contains_key(header::ETAG) {
+        snapshot.insert("ETag".to_string(), json!(ETAG));
+    }
+    if !bytes.is_empty() {
+        let mut body = serde_json::from_slice(&bytes).unwrap();
+        normalize(&mut body);
+        snapshot.insert("Body".to_string(), body);
+    }
+    Value::Object(snapshot)
+}
+
+/// Compares `response` to the golden file `golden/<name>.json`. With
+/// UPDATE_GOLDEN set, the golden file is rewritten instead.
+pub async fn assert_golden(name: &str, response: Response) {
+    let actual = snapshot(response).await;
+    let path = path(name);
+    if std::env::var_os(UPDATE).is_some() {
+        let contents = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+        std::fs::write(&path, contents).unwrap();
+        return;
+    }
+
+    let contents = std::fs::read_to_string(&path).unwrap_or_else(|error| {
+        panic!(
+            "{}: {} (set {} to create it)",
+            path.display(),
+            error,
+            UPDATE
+        )
+    });
+    let expected: Value = serde_json::from_str(&contents).unwrap();
+    assert_eq!(
+        actual,
+        expected,
+        "response differs from {} (set {} to update it)",
+        path.display(),
+        UPDATE
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_etags_and_date_times_of_the_schema() {
+        let mut value = json!({
+            "@odata.etag": "\"0123456789abcdef\"",
+            "@odata.type": "#SessionCollection.SessionCollection",
+            "CreatedTime": "2023-06-01T12:00:00Z",
+            "Members": [
+                {
+                    "@odata.type": "#Session.v1_6_0.Session",
+                    "CreatedTime": "2023-06-01T12:00:00.123+02:00",
+                    "Name": "2023-06-01T12:00:00Z",
+                },
+            ],
+        });
+        normalize(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "@odata.etag": ETAG,
+                "@odata.type": "#SessionCollection.SessionCollection",
+                "CreatedTime": "2023-06-01T12:00:00Z",
+                "Members": [
+                    {
+                        "@odata.type": "#Session.v1_6_0.Session",
+                        "CreatedTime": TIMESTAMP,
+                        "Name": "2023-06-01T12:00:00Z",
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn nested_objects_inherit_the_schema() {
+        let mut value = json!({
+            "@odata.type": "#Event.v1_7_0.Event",
+            "Events": [{ "EventTimestamp": "2023-06-01T12:00:00Z" }],
+        });
+        normalize(&mut value);
+        assert_eq!(value["Events"][0]["EventTimestamp"], TIMESTAMP);
+    }
+}
@@ -0,0 +1,145 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::PathBuf;
+
+/// Optional sandboxing, applied once the configuration is loaded and before
+/// the runtime starts, so every thread the service creates inherits it.
+/// Landlock only restricts the calling thread and the threads it creates, so
+/// it can't wait until the listeners are bound. Neither layer restricts
+/// sockets, and the certificate and key are read again whenever the server
+/// restarts, so they must be readable under landlock regardless.
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+pub struct Configuration {
+    landlock: Option<Landlock>,
+    seccomp: Option<Seccomp>,
+}
+
+/// Filesystem access. Anything not listed is inaccessible, so this must
+/// cover the certificate and key, PAM configuration or password file,
+/// recording directory, etc.
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+pub struct Landlock {
+    read: Vec<PathBuf>,
+    write: Vec<PathBuf>,
+}
+
+/// Denies syscalls the service never needs, e.g. module loading, mount,
+/// ptrace and reboot.
+#[derive(serde::Deserialize)]
+#[serde(default)]
+pub struct Seccomp {
+    /// PAM runs helpers such as unix_chkpwd, so exec is allowed by default.
+    #[serde(rename = "allow-exec")]
+    allow_exec: bool,
+}
+
+impl Default for Seccomp {
+    fn default() -> Self {
+        Seccomp { allow_exec: true }
+    }
+}
+
+#[cfg(all(feature = "hardening", target_os = "linux"))]
+pub fn apply(config: &Configuration) -> anyhow::Result<()> {
+    if let Some(landlock) = &config.landlock {
+        apply_landlock(landlock)?;
+    }
+    if let Some(seccomp) = &config.seccomp {
+        apply_seccomp(seccomp)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(all(feature = "hardening", target_os = "linux")))]
+pub fn apply(config: &Configuration) -> anyhow::Result<()> {
+    if config.landlock.is_some() || config.seccomp.is_some() {
+        anyhow::bail!("hardening is configured, but this build does not support it");
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "hardening", target_os = "linux"))]
+fn apply_landlock(config: &Landlock) -> anyhow::Result<()> {
+    use landlock::{
+        path_beneath_rules, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr,
+        RulesetStatus, ABI,
+    };
+
+    let abi = ABI::V2;
+    let status = Ruleset::new()
+        .handle_access(AccessFs::from_all(abi))?
+        .create()?
+        .add_rules(path_beneath_rules(&config.read, AccessFs::from_read(abi)))?
+        .add_rules(path_beneath_rules(&config.write, AccessFs::from_all(abi)))?
+        .restrict_self()?;
+    match status.ruleset {
+        RulesetStatus::FullyEnforced => tracing::info!("landlock ruleset enforced"),
+        RulesetStatus::PartiallyEnforced => {
+            tracing::warn!("landlock ruleset only partially enforced by this kernel")
+        }
+        RulesetStatus::NotEnforced => {
+            tracing::warn!("landlock is not supported by this kernel")
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "hardening", target_os = "linux"))]
+fn apply_seccomp(config: &Seccomp) -> anyhow::Result<()> {
+    use seccompiler::{BpfProgram, SeccompAction, SeccompFilter, SeccompRule};
+    use std::collections::BTreeMap;
+
+    let mut denied = vec![
+        libc::SYS_ptrace,
+        libc::SYS_process_vm_readv,
+        libc::SYS_process_vm_writev,
+        libc::SYS_mount,
+        libc::SYS_umount2,
+        libc::SYS_pivot_root,
+        libc::SYS_chroot,
+        libc::SYS_swapon,
+        libc::SYS_swapoff,
+        libc::SYS_reboot,
+        libc::SYS_kexec_load,
+        libc::SYS_init_module,
+        libc::SYS_finit_module,
+        libc::SYS_delete_module,
+        libc::SYS_bpf,
+        libc::SYS_perf_event_open,
+    ];
+    if !config.allow_exec {
+        denied.extend([libc::SYS_execve, libc::SYS_execveat]);
+    }
+
+    // An empty rule list matches the syscall unconditionally.
+    let rules: BTreeMap<i64, Vec<SeccompRule>> = denied
+        .into_iter()
+        .map(|syscall| (syscall, vec![]))
+        .collect();
+    let filter = SeccompFilter::new(
+        rules,
+        SeccompAction::Allow,
+        SeccompAction::Errno(libc::EPERM as u32),
+        std::env::consts::ARCH.try_into()?,
+    )?;
+    let program: BpfProgram = filter.try_into()?;
+    seccompiler::apply_filter_all_threads(&program)?;
+    tracing::info!("seccomp filter installed");
+    Ok(())
+}
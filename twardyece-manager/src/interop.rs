@@ -0,0 +1,239 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use axum::{
+    body::Body,
+    http::{header, Request},
+    Router,
+};
+use base64::Engine;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fs::File,
+    path::PathBuf,
+};
+use tower::ServiceExt;
+
+/// Upper bound on the number of resources read while checking, in case a
+/// collection is very large.
+const MAX_RESOURCES: usize = 1000;
+
+/// Checks the service against a Redfish Interoperability Profile (DSP0272)
+/// at startup.
+#[derive(serde::Deserialize)]
+pub struct Configuration {
+    /// Path to the profile JSON document.
+    profile: PathBuf,
+    /// Refuse to start if the service does not meet the profile, instead of
+    /// only logging what is missing.
+    #[serde(default)]
+    strict: bool,
+    /// Credentials used to read the resources that are checked. Without
+    /// them, only resources that need no authentication are seen.
+    username: Option<String>,
+    password: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Profile {
+    profile_name: String,
+    #[serde(default)]
+    resources: BTreeMap<String, ResourceRequirement>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResourceRequirement {
+    read_requirement: Option<String>,
+    #[serde(default)]
+    property_requirements: BTreeMap<String, PropertyRequirement>,
+    #[serde(default)]
+    action_requirements: BTreeMap<String, Value>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PropertyRequirement {
+    read_requirement: Option<String>,
+    #[serde(default)]
+    property_requirements: BTreeMap<String, PropertyRequirement>,
+}
+
+/// Requirements default to Mandatory when the profile does not say.
+fn is_mandatory(requirement: &Option<String>) -> bool {
+    requirement.as_deref().unwrap_or("Mandatory") == "Mandatory"
+}
+
+/// Extracts the schema name, e.g. ComputerSystem, from an @odata.type like
+/// `#ComputerSystem.v1_20_0.ComputerSystem`.
+fn schema(resource: &Value) -> Option<&str> {
+    resource
+        .get("@odata.type")?
+        .as_str()?
+        .trim_start_matches('#')
+        .split('.')
+        .next()
+}
+
+fn links(value: &Value, links: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                match (key.as_str(), value) {
+                    ("@odata.id", Value::String(id)) => links.push(id.clone()),
+                    _ => self::links(value, links),
+                }
+            }
+        }
+        Value::Array(array) => array.iter().for_each(|value| self::links(value, links)),
+        _ => {}
+    }
+}
+
+fn check_properties(
+    resource: &Value,
+    requirements: &BTreeMap<String, PropertyRequirement>,
+    path: &str,
+    gaps: &mut Vec<String>,
+) {
+    for (name, requirement) in requirements {
+        let path = if path.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", path, name)
+        };
+        match resource.get(name) {
+            Some(Value::Null) | None if is_mandatory(&requirement.read_requirement) => {
+                gaps.push(format!("missing property {}", path))
+            }
+            Some(Value::Array(items)) => items.iter().for_each(|item| {
+                check_properties(item, &requirement.property_requirements, &path, gaps)
+            }),
+            Some(value) => check_properties(value, &requirement.property_requirements, &path, gaps),
+            None => {}
+        }
+    }
+}
+
+fn check_resource(resource: &Value, requirement: &ResourceRequirement) -> Vec<String> {
+    let mut gaps = Vec::new();
+    check_properties(resource, &requirement.property_requirements, "", &mut gaps);
+    let actions = resource
+        .get("Actions")
+        .and_then(|actions| actions.as_object());
+    for action in requirement.action_requirements.keys() {
+        let suffix = format!(".{}", action);
+        let found = actions.is_some_and(|actions| actions.keys().any(|key| key.ends_with(&suffix)));
+        if !found {
+            gaps.push(format!("missing action {}", action));
+        }
+    }
+    gaps
+}
+
+/// Reads every resource reachable from `service_root` through `app`, and
+/// compares them with the profile. Returns an error if the profile cannot be
+/// read, or if the service falls short of it and the check is strict.
+pub async fn check(config: Configuration, app: Router, service_root: &str) -> anyhow::Result<()> {
+    let file = File::open(&config.profile)
+        .with_context(|| format!("failed to open {}", config.profile.display()))?;
+    let profile: Profile = serde_json::from_reader(file)?;
+    let authorization = config.username.map(|username| {
+        let credentials = username + ":" + config.password.as_deref().unwrap_or_default();
+        "Basic ".to_string() + &base64::engine::general_purpose::STANDARD.encode(credentials)
+    });
+
+    // Only resources within the service are followed.
+    let prefix = service_root.trim_end_matches('/');
+    let mut found: HashMap<String, Vec<(String, Value)>> = HashMap::new();
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::from([service_root.to_string()]);
+    while let Some(path) = queue.pop_front() {
+        if visited.len() >= MAX_RESOURCES || !visited.insert(path.clone()) {
+            continue;
+        }
+
+        let mut request = Request::get(&path);
+        if let Some(authorization) = &authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        let response = app.clone().oneshot(request.body(Body::empty())?).await?;
+        if !response.status().is_success() {
+            tracing::debug!("interop check: {} returned {}", path, response.status());
+            continue;
+        }
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        let resource: Value = match serde_json::from_slice(&body) {
+            Ok(resource) => resource,
+            Err(_) => continue,
+        };
+
+        let mut references = Vec::new();
+        links(&resource, &mut references);
+        queue.extend(
+            references
+                .into_iter()
+                .filter(|id| id.starts_with(prefix) && !id.contains('#')),
+        );
+        if let Some(schema) = schema(&resource) {
+            found
+                .entry(schema.to_string())
+                .or_default()
+                .push((path, resource));
+        }
+    }
+
+    let mut gaps = Vec::new();
+    for (name, requirement) in &profile.resources {
+        match found.get(name) {
+            None if is_mandatory(&requirement.read_requirement) => {
+                gaps.push(format!("{}: no instances found", name))
+            }
+            None => {}
+            Some(instances) => {
+                for (path, resource) in instances {
+                    gaps.extend(
+                        check_resource(resource, requirement)
+                            .into_iter()
+                            .map(|gap| format!("{} {}: {}", name, path, gap)),
+                    );
+                }
+            }
+        }
+    }
+
+    if gaps.is_empty() {
+        tracing::info!(
+            "service meets interoperability profile {}",
+            profile.profile_name
+        );
+        return Ok(());
+    }
+    for gap in &gaps {
+        tracing::warn!("interoperability profile {}: {}", profile.profile_name, gap);
+    }
+    if config.strict {
+        anyhow::bail!(
+            "service does not meet interoperability profile {} ({} gaps)",
+            profile.profile_name,
+            gaps.len()
+        );
+    }
+    Ok(())
+}
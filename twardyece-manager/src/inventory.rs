@@ -0,0 +1,175 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{endpoint::DummySystem, links};
+use rand::{distributions::Alphanumeric, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use redfish_codegen::models::{odata_v4, resource};
+use std::collections::HashSet;
+
+/// Namespace for UUIDs derived from system names, so that the same name
+/// always maps to the same id across restarts.
+const SYSTEM_ID_NAMESPACE: uuid::Uuid = uuid::uuid!("5c6ea4a2-6a0e-4a39-9d53-0b7f0ae8a3f1");
+
+/// How the Id (and therefore the URI) of each system is derived.
+#[derive(Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IdStrategy {
+    /// Use the configured name verbatim.
+    #[default]
+    Name,
+    /// Lowercase the name and replace anything but letters and digits by '-'.
+    Slug,
+    /// A name-based (version 5) UUID.
+    Uuid,
+    /// 1, 2, 3, ... in configuration order.
+    Sequence,
+}
+
+impl IdStrategy {
+    fn id(&self, name: &str, index: usize) -> String {
+        match self {
+            IdStrategy::Name => name.to_string(),
+            IdStrategy::Slug => name
+                .to_lowercase()
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+                .join("-"),
+            IdStrategy::Uuid => {
+                uuid::Uuid::new_v5(&SYSTEM_ID_NAMESPACE, name.as_bytes()).to_string()
+            }
+            IdStrategy::Sequence => (index + 1).to_string(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct Member {
+    pub name: String,
+}
+
+/// Generates systems with random but plausible inventory, for demos and for
+/// testing clients against large collections.
+#[derive(serde::Deserialize)]
+pub struct Simulation {
+    /// Number of systems to generate.
+    pub count: usize,
+    /// Systems are named `<prefix><n>`.
+    #[serde(rename = "name-prefix", default = "Simulation::default_prefix")]
+    pub name_prefix: String,
+    /// Seed for the generator, so that a profile always yields the same
+    /// inventory.
+    #[serde(default)]
+    pub seed: u64,
+}
+
+const SIMULATED_MODELS: [(&str, &str); 4] = [
+    ("Contoso", "CX-2200"),
+    ("Contoso", "CX-4400"),
+    ("Fabrikam", "Rackline R10"),
+    ("Northwind", "NW-1U"),
+];
+
+impl Simulation {
+    fn default_prefix() -> String {
+        "sim-".to_string()
+    }
+
+    fn systems(&self) -> Vec<(String, DummySystem)> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        (1..=self.count)
+            .map(|index| {
+                let (manufacturer, model) = SIMULATED_MODELS.choose(&mut rng).unwrap();
+                let serial_number: String = (&mut rng)
+                    .sample_iter(&Alphanumeric)
+                    .take(10)
+                    .map(|c| char::from(c).to_ascii_uppercase())
+                    .collect();
+                let power_state = if rng.gen_bool(0.8) {
+                    resource::PowerState::On
+                } else {
+                    resource::PowerState::Off
+                };
+                let system = DummySystem {
+                    manufacturer: Some(manufacturer.to_string()),
+                    model: Some(model.to_string()),
+                    serial_number: Some(serial_number),
+                    power_state,
+                    ..Default::default()
+                };
+                (self.name_prefix.clone() + &index.to_string(), system)
+            })
+            .collect()
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(default)]
+pub struct Configuration {
+    #[serde(rename = "id-strategy")]
+    pub id_strategy: IdStrategy,
+    pub members: Vec<Member>,
+    pub simulation: Option<Simulation>,
+    /// Let clients create systems with a PUT to an unused id.
+    #[serde(rename = "allow-create")]
+    pub allow_create: bool,
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Configuration {
+            id_strategy: IdStrategy::default(),
+            members: vec![Member {
+                name: "1".to_string(),
+            }],
+            simulation: None,
+            allow_create: false,
+        }
+    }
+}
+
+impl Configuration {
+    /// Create the systems described by the configuration as members of the
+    /// collection at `collection`. Fails if two systems would share an id.
+    pub fn systems(&self, collection: &odata_v4::Id) -> anyhow::Result<Vec<DummySystem>> {
+        let configured = self
+            .members
+            .iter()
+            .map(|member| (member.name.clone(), DummySystem::default()));
+        let simulated = self
+            .simulation
+            .iter()
+            .flat_map(|simulation| simulation.systems());
+
+        let mut ids = HashSet::new();
+        configured
+            .chain(simulated)
+            .enumerate()
+            .map(|(index, (name, system))| {
+                let id = self.id_strategy.id(&name, index);
+                if id.is_empty() || !ids.insert(id.clone()) {
+                    anyhow::bail!("system {:?} has a duplicate or empty id {:?}", name, id);
+                }
+                Ok(DummySystem {
+                    odata_id: links::member(collection, &id),
+                    id: resource::Id(id),
+                    name: resource::Name(name),
+                    ..system
+                })
+            })
+            .collect()
+    }
+}
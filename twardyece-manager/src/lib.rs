@@ -0,0 +1,547 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::Body,
+    http::Request,
+    middleware::Next,
+    response::Redirect,
+    routing::MethodRouter,
+    Router,
+};
+use redfish_codegen::models::{odata_v4, resource};
+use seuss::{
+    auth::{CombinedAuthenticationProxy, Role},
+    routing,
+    service::{self, session_manager::InMemorySessionManager},
+};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tower_http::trace::TraceLayer;
+
+pub mod action;
+pub mod approval;
+pub mod auth;
+pub mod batch;
+pub mod dbus;
+pub mod endpoint;
+pub mod environment;
+pub mod events;
+#[cfg(test)]
+mod golden;
+pub mod hardening;
+pub mod interop;
+pub mod inventory;
+pub mod links;
+pub mod middleware;
+pub mod migration;
+pub mod mqtt;
+pub mod openapi;
+pub mod power;
+pub mod prerender;
+pub mod registry;
+pub mod supervisor;
+#[cfg(feature = "ui")]
+pub mod ui;
+pub mod webhook;
+
+#[derive(serde::Deserialize)]
+pub struct Configuration {
+    #[serde(rename = "role-map", default)]
+    pub role_map: HashMap<Role, String>,
+    #[serde(default)]
+    pub authentication: auth::Configuration,
+    /// Path prefix under which the whole service is mounted, e.g. `/bmc1`
+    /// when a reverse proxy forwards `/bmc1/redfish/v1` to this service.
+    #[serde(rename = "base-path", default)]
+    pub base_path: String,
+    #[serde(rename = "if-match", default)]
+    pub if_match: middleware::IfMatchConfiguration,
+    /// Handling of @odata.id, @odata.type and Id supplied on create or
+    /// replace.
+    #[serde(rename = "client-identifiers", default)]
+    pub client_identifiers: middleware::IdentifierPolicy,
+    /// Server and X-Manager-Instance headers sent with every response.
+    #[serde(default)]
+    pub identification: middleware::IdentificationConfiguration,
+    #[serde(rename = "read-only", default)]
+    pub read_only: ReadOnly,
+    /// Routes, in addition to those required by the specification, that may
+    /// be requested without credentials.
+    #[serde(rename = "anonymous-routes", default)]
+    pub anonymous_routes: Vec<String>,
+    #[serde(default)]
+    pub systems: inventory::Configuration,
+    /// Translated message registries, selected with Accept-Language.
+    pub localization: Option<middleware::LocalizationConfiguration>,
+    /// Record requests and responses to disk, for debugging.
+    pub recording: Option<middleware::RecordingConfiguration>,
+    /// Faults to inject into responses, for testing clients.
+    #[serde(rename = "fault-injection", default)]
+    pub fault_injection: Vec<middleware::Fault>,
+    #[serde(default)]
+    pub hardening: hardening::Configuration,
+    #[serde(rename = "interop-profile")]
+    pub interop_profile: Option<interop::Configuration>,
+    /// HTTP endpoints notified of state transitions, such as power changes.
+    #[serde(default)]
+    pub webhooks: Vec<webhook::Webhook>,
+    /// Broker that state transitions are published to.
+    pub mqtt: Option<mqtt::Configuration>,
+    /// Serve org.twardyece.Manager1 on the system bus.
+    #[serde(default)]
+    pub dbus: bool,
+    /// Destructive operations that a second user must approve.
+    pub confirmation: Option<approval::Configuration>,
+    pub server: redfish_service::Configuration,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+pub struct ReadOnly {
+    /// Reject every modifying request, regardless of who sends it, except
+    /// logging in and out.
+    enabled: bool,
+    /// Roles whose users are only granted the ReadOnly role.
+    roles: Vec<Role>,
+}
+
+/// The service built from the configuration, and what the tasks running
+/// alongside it need.
+pub struct Service {
+    pub app: Router,
+    pub link: links::LinkBuilder,
+    pub members: endpoint::Members,
+    pub events: events::Events,
+    pub restrictions: dbus::Restrictions,
+}
+
+/// Builds the service described by `config`, without starting anything.
+pub async fn service(config: Configuration) -> anyhow::Result<Service> {
+    let link = links::LinkBuilder::new(&config.base_path);
+
+    let service_root = endpoint::ServiceRoot::new(
+        odata_v4::Id(
+            link.id(links::SERVICE_ROOT)
+                .0
+                .trim_end_matches('/')
+                .to_string(),
+        ),
+        resource::Name("Basic Redfish Service".to_string()),
+        resource::Id("example-basic".to_string()),
+    )
+    .enable_systems(link.id(links::SYSTEMS))
+    .enable_sessions(link.id(links::SESSION_SERVICE), link.id(links::SESSIONS))
+    .enable_only_member_query();
+
+    let service_document = routing::OData::new()
+        .enable_systems()
+        .enable_session_service()
+        .enable_sessions();
+
+    let backend = auth::Authenticator::new(config.authentication.backend, config.role_map)?;
+    let password_file = match &backend {
+        auth::Authenticator::PasswordFile(accounts) => Some(accounts.clone()),
+        _ => None,
+    };
+    let cache = auth::CachingAuthenticator::new(
+        backend,
+        Duration::from_secs(config.authentication.cache_ttl),
+    );
+    // The pool is outermost, as the verdicts it prepares for seuss are the
+    // final ones.
+    let authenticator = auth::BlockingPoolAuthenticator::new(
+        auth::ReadOnlyAuthenticator::new(cache.clone(), config.read_only.roles),
+        config.authentication.workers,
+        config.authentication.queue_depth,
+        Duration::from_secs(config.authentication.timeout),
+    );
+    let session_collection =
+        InMemorySessionManager::new(authenticator.clone(), link.id(links::SESSIONS));
+    let registry = registry::Registry::default();
+    let registry_routes = registry::routes(
+        registry.clone(),
+        authenticator.clone(),
+        links::RESOURCE_REGISTRY,
+        link.clone(),
+    );
+    let proxy =
+        CombinedAuthenticationProxy::new(session_collection.clone(), authenticator.clone());
+    let approvals = config.confirmation.map(|confirmation| {
+        approval::Approvals::new(confirmation, proxy.clone(), link.clone())
+    });
+
+    let events = events::Events::default();
+    let systems_id = link.id(links::SYSTEMS);
+    let systems = endpoint::Systems::new(
+        systems_id.clone(),
+        resource::Name("Computer System Collection".to_string()),
+        config.systems.systems(&systems_id)?,
+        proxy.clone(),
+    )
+    .allow_create(config.systems.allow_create)
+    .publish_to(events.clone());
+    let members = systems.members();
+
+    let static_resources = openapi::Routes::default()
+        .route(
+            links::REDFISH,
+            routing::RedfishVersions::default().into(),
+            openapi::GET,
+        )
+        .route(
+            links::SERVICE_ROOT,
+            routing::ServiceRoot::new(service_root).into(),
+            openapi::SERVICE_ROOT,
+        )
+        .route(links::ODATA, service_document.into(), openapi::GET)
+        .route(links::METADATA, routing::Metadata.into(), openapi::GET);
+    let static_resources = prerender::prerender(static_resources).await?;
+
+    // Merged outside the layers below, but described along with the rest.
+    #[cfg(feature = "ui")]
+    let ui = ui::routes();
+
+    let anonymous = middleware::AnonymousRoutes::new(config.anonymous_routes);
+    let service_root_redirect = Redirect::permanent(&link.id(links::SERVICE_ROOT).0);
+    let routes = openapi::Routes::default()
+        .merge(static_resources)
+        .route(
+            links::SERVICE_ROOT.trim_end_matches('/'),
+            axum::routing::get(move || std::future::ready(service_root_redirect.clone())),
+            openapi::GET,
+        )
+        .route(
+            links::SYSTEMS,
+            routing::Systems::new(systems.clone()).into(),
+            openapi::SYSTEMS,
+        )
+        .route(
+            &links::members(links::SYSTEMS, "name"),
+            MethodRouter::from(routing::computer_system_detail::ComputerSystemDetail::new(
+                systems.clone(),
+            ))
+            .layer(axum::middleware::from_fn_with_state(
+                systems.members(),
+                middleware::created_by_put,
+            ))
+            .layer(axum::middleware::from_fn_with_state(
+                endpoint::ACTIONS,
+                action::advertise,
+            )),
+            openapi::SYSTEM,
+        )
+        .route(
+            &(links::members(links::SYSTEMS, "name") + "/" + power::RESET_ACTION_INFO.id),
+            action::info(&power::RESET_ACTION_INFO, {
+                let members = systems.members();
+                move |id| members.contains(id)
+            }),
+            openapi::GET,
+        )
+        .route(
+            &links::action(&links::members(links::SYSTEMS, "name"), "ComputerSystem.Reset"),
+            action::target_with_info(
+                routing::computer_system_detail::reset::ResetRouter::new(systems).into(),
+                &power::RESET_ACTION_INFO,
+            ),
+            openapi::RESET,
+        )
+        .route(
+            &links::action(
+                &links::members(links::SYSTEMS, "name"),
+                "Oem/TwardyEce.Restore",
+            ),
+            endpoint::restore(members.clone(), proxy.clone()),
+            openapi::POST,
+        )
+        .route(
+            links::SESSION_SERVICE,
+            routing::SessionService::new(service::SessionService::new(
+                link.id(links::SESSION_SERVICE),
+                resource::Name("Stub Session Service".to_string()),
+                link.id(links::SESSIONS),
+                proxy.clone(),
+            ))
+            .into(),
+            openapi::SESSION_SERVICE,
+        )
+        .route(
+            links::SESSIONS,
+            routing::sessions::Sessions::new(service::SessionCollection::new(
+                link.id(links::SESSIONS),
+                resource::Name("Session Collection".to_string()),
+                proxy.clone(),
+                session_collection.clone(),
+            ))
+            .into(),
+            openapi::SESSIONS,
+        )
+        // Session members are served by seuss, along with the collection.
+        .describe(&links::members(links::SESSIONS, "id"), openapi::SESSION)
+        .describe(links::BATCH, openapi::POST)
+        .merge(registry_routes);
+    let routes = match &password_file {
+        Some(accounts) => routes.route(
+            &links::members(links::ACCOUNTS, "name"),
+            endpoint::Accounts::new(accounts.clone(), cache, proxy.clone(), link.clone()).into(),
+            openapi::ACCOUNT,
+        ),
+        None => routes,
+    };
+    let routes = if approvals.is_some() {
+        approval::describe(routes)
+    } else {
+        routes
+    };
+    #[cfg(feature = "ui")]
+    let routes = routes.describe_all(&ui);
+    let routes = routes.serve_document(link.clone(), anonymous.clone(), registry.clone());
+    registry.reserve(routes.paths());
+    let app = routes
+        .into_router()
+        .fallback(move |request: Request<Body>| registry.clone().dispatch(request));
+
+    // Here as well as outside the approvals, for the held requests that they
+    // replay with the credentials of the requester.
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        authenticator.clone(),
+        middleware::prepare_credentials,
+    ));
+
+    let app = if config.read_only.enabled {
+        app.layer(axum::middleware::from_fn(middleware::read_only))
+    } else {
+        app
+    };
+
+    let restrictions = dbus::Restrictions {
+        read_only: config.read_only.enabled,
+        approval_reset_types: approvals
+            .as_ref()
+            .map(|approvals| approvals.reset_types().to_vec())
+            .unwrap_or_default(),
+    };
+    let app = match approvals {
+        Some(approvals) => app.clone().layer(axum::middleware::from_fn(
+            move |request: Request<Body>, next: Next<Body>| {
+                approval::confirm(app.clone(), approvals.clone(), request, next)
+            },
+        )),
+        None => app,
+    };
+
+    let app = match password_file {
+        Some(accounts) => app.layer(axum::middleware::from_fn_with_state(
+            (accounts, proxy.clone(), link.clone()),
+            middleware::require_password_change,
+        )),
+        None => app,
+    };
+
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        (anonymous, proxy),
+        middleware::require_credentials,
+    ));
+
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        authenticator,
+        middleware::prepare_credentials,
+    ));
+
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        config.client_identifiers,
+        middleware::identifiers,
+    ));
+
+    let etags = middleware::ETags::new(config.if_match);
+    let app = app.clone().layer(axum::middleware::from_fn(
+        move |request: Request<Body>, next: Next<Body>| {
+            middleware::etags(app.clone(), etags.clone(), request, next)
+        },
+    ));
+
+    let long_poll = middleware::LongPoll::new(events.clone());
+    let app = app.clone().layer(axum::middleware::from_fn(
+        move |request: Request<Body>, next: Next<Body>| {
+            middleware::long_poll(app.clone(), long_poll.clone(), request, next)
+        },
+    ));
+
+    // Outside the ETag and long polling layers, so that the request for a
+    // member returned for ?only goes through them like any other request.
+    let query_link = link.clone();
+    let app = app.clone().layer(axum::middleware::from_fn(
+        move |request: Request<Body>, next: Next<Body>| {
+            middleware::query_parameters(app.clone(), query_link.clone(), request, next)
+        },
+    ));
+
+    let app = match config.localization {
+        Some(localization) => app.layer(axum::middleware::from_fn_with_state(
+            middleware::Localization::new(localization)?,
+            middleware::localize,
+        )),
+        None => app,
+    };
+
+    let app = match config.recording {
+        Some(recording) => app.layer(axum::middleware::from_fn_with_state(
+            middleware::Recorder::new(recording)?,
+            middleware::record,
+        )),
+        None => app,
+    };
+
+    let app = if config.fault_injection.is_empty() {
+        app
+    } else {
+        app.layer(axum::middleware::from_fn_with_state(
+            Arc::new(config.fault_injection),
+            middleware::inject_faults,
+        ))
+    };
+
+    #[cfg(feature = "xml")]
+    let app = app.layer(axum::middleware::from_fn(middleware::xml_errors));
+
+    #[cfg(feature = "ui")]
+    let app = app.merge(ui.into_router());
+
+    let app = if link.base_path().is_empty() {
+        app
+    } else {
+        Router::new().nest(link.base_path(), app)
+    }
+    .layer(axum::middleware::from_fn(middleware::describedby))
+    .layer(axum::middleware::from_fn_with_state(
+        middleware::Challenge::new(&config.authentication.realm)?,
+        middleware::challenge,
+    ))
+    .layer(axum::middleware::from_fn_with_state(
+        middleware::Identification::new(config.identification)?,
+        middleware::identify,
+    ))
+    .layer(TraceLayer::new_for_http());
+
+    // Outermost, so that every operation in a batch goes through the same
+    // layers as a request sent on its own.
+    let batch_link = link.clone();
+    let app = app.clone().layer(axum::middleware::from_fn(
+        move |request: Request<Body>, next: Next<Body>| {
+            batch::batch(app.clone(), batch_link.clone(), request, next)
+        },
+    ));
+
+    Ok(Service {
+        app,
+        link,
+        members,
+        events,
+        restrictions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{header, Method, StatusCode};
+    use base64::Engine;
+    use tower::ServiceExt;
+
+    const CONFIG: &str = r#"
+authentication:
+  backend:
+    type: static
+    accounts:
+      - username: admin
+        password: admin
+        role: Administrator
+anonymous-routes: [/redfish/v1/Public/*]
+server:
+  address: 127.0.0.1
+  ports:
+    http: 3000
+    https: 3001
+  certificate-file: cert.pem
+  key-file: key.pem
+"#;
+
+    fn basic(username: &str, password: &str) -> String {
+        let credentials = format!("{}:{}", username, password);
+        "Basic ".to_string() + &base64::engine::general_purpose::STANDARD.encode(credentials)
+    }
+
+    async fn send(method: Method, path: &str, authorization: Option<String>) -> StatusCode {
+        let config = serde_yaml::from_str(CONFIG).unwrap();
+        let app = service(config).await.unwrap().app;
+        let mut request = Request::builder().method(method).uri(path);
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        let request = request.body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn protected_routes_need_valid_credentials() {
+        let requests = [
+            (Method::GET, links::SYSTEMS, StatusCode::OK),
+            (Method::GET, links::SESSIONS, StatusCode::OK),
+            (
+                Method::DELETE,
+                "/redfish/v1/SessionService/Sessions/1",
+                StatusCode::NOT_FOUND,
+            ),
+        ];
+        for (method, path, authenticated) in requests {
+            let status = send(method.clone(), path, None).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{} {}", method, path);
+            let status = send(method.clone(), path, Some(basic("admin", "wrong"))).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{} {}", method, path);
+            let status = send(method.clone(), path, Some(basic("admin", "admin"))).await;
+            assert_eq!(status, authenticated, "{} {}", method, path);
+        }
+    }
+
+    #[tokio::test]
+    async fn public_routes_pass() {
+        let paths = [
+            links::REDFISH,
+            links::SERVICE_ROOT,
+            links::ODATA,
+            links::METADATA,
+            openapi::PATH,
+        ];
+        for path in paths {
+            let status = send(Method::GET, path, None).await;
+            assert_eq!(status, StatusCode::OK, "{}", path);
+        }
+        let status = send(Method::GET, "/redfish/v1/Public/Status", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn only_reads_of_public_routes_pass() {
+        let requests = [
+            (Method::PATCH, links::SERVICE_ROOT),
+            (Method::DELETE, "/redfish/v1/Public/Status"),
+        ];
+        for (method, path) in requests {
+            let status = send(method.clone(), path, None).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{} {}", method, path);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use redfish_codegen::models::odata_v4;
+
+// Paths at which the resources of the service are mounted, relative to the
+// base path. Routes and links are both built from these, so that they can't
+// drift apart.
+pub const REDFISH: &str = "/redfish";
+pub const SERVICE_ROOT: &str = "/redfish/v1/";
+pub const ODATA: &str = "/redfish/v1/odata";
+pub const METADATA: &str = "/redfish/v1/$metadata";
+pub const BATCH: &str = "/redfish/v1/$batch";
+pub const SYSTEMS: &str = "/redfish/v1/Systems";
+pub const SESSION_SERVICE: &str = "/redfish/v1/SessionService";
+pub const SESSIONS: &str = "/redfish/v1/SessionService/Sessions";
+pub const ACCOUNTS: &str = "/redfish/v1/AccountService/Accounts";
+pub const RESOURCE_REGISTRY: &str = "/redfish/v1/Oem/TwardyEce/ResourceRegistry";
+pub const APPROVALS: &str = "/redfish/v1/Oem/TwardyEce/Approvals";
+
+/// Builds the @odata.id of resources from the paths they are mounted at, so
+/// that links include the base path the service is mounted under.
+#[derive(Clone)]
+pub struct LinkBuilder {
+    base_path: String,
+}
+
+impl LinkBuilder {
+    pub fn new(base_path: &str) -> Self {
+        LinkBuilder {
+            base_path: base_path.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// The base path, without a trailing '/'. Empty when the service is
+    /// mounted at the root.
+    pub fn base_path(&self) -> &str {
+        &self.base_path
+    }
+
+    /// The id of the resource mounted at `path`.
+    pub fn id(&self, path: &str) -> odata_v4::Id {
+        odata_v4::Id(self.base_path.clone() + path)
+    }
+}
+
+/// The route matching the members of the collection at `collection`, with
+/// the id of the member captured as `parameter`.
+pub fn members(collection: &str, parameter: &str) -> String {
+    format!("{}/:{}", collection, parameter)
+}
+
+/// The id of the member `id` of the collection at `collection`.
+pub fn member(collection: &odata_v4::Id, id: &str) -> odata_v4::Id {
+    odata_v4::Id(format!("{}/{}", collection.0, id))
+}
+
+/// The target of the action `name` of the resource at `resource`.
+pub fn action(resource: &str, name: &str) -> String {
+    format!("{}/Actions/{}", resource, name)
+}
@@ -14,119 +14,135 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use axum::{
-    response::Redirect,
-    Router,
-};
 use clap::Parser;
-use redfish_codegen::models::{resource, odata_v4};
-use seuss::{
-    auth::{pam::LinuxPamAuthenticator, CombinedAuthenticationProxy, Role},
-    routing,
-    service::{self, session_manager::InMemorySessionManager},
+use std::{path::PathBuf, time::Duration};
+use twardyece_manager::{
+    dbus, environment, hardening, interop, links, migration, mqtt, supervisor, webhook,
+    Configuration, Service,
 };
-use std::{collections::HashMap, fs::File};
-use tower_http::trace::TraceLayer;
-
-mod auth;
-mod endpoint;
 
 #[derive(Parser)]
 struct Args {
-    /// Configuration file
-    #[clap(value_parser, short, long)]
-    config: String,
+    /// Configuration file. Values may also be set, or overridden, with
+    /// TWARDYECE_MANAGER_* environment variables.
+    #[clap(value_parser, short, long, env = "TWARDYECE_MANAGER_CONFIG")]
+    config: Option<PathBuf>,
+    /// Directory holding all state, for deployments without a configuration
+    /// file. The certificate, key and password file default to paths in it.
+    #[clap(value_parser, long, env = "TWARDYECE_MANAGER_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+    #[clap(
+        value_enum,
+        long,
+        env = "TWARDYECE_MANAGER_LOG_FORMAT",
+        default_value = "text"
+    )]
+    log_format: LogFormat,
+    #[clap(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(serde::Deserialize)]
-struct Configuration {
-    #[serde(rename = "role-map")]
-    role_map: HashMap<Role, String>,
-    server: redfish_service::Configuration,
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Write an example configuration, documenting every section, and exit.
+    GenerateConfig {
+        /// File to write the configuration to, instead of stdout.
+        #[clap(value_parser, short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Upgrade a configuration file to the current schema version, and exit.
+    /// Comments are not preserved.
+    MigrateConfig {
+        #[clap(value_parser)]
+        input: PathBuf,
+        /// File to write the configuration to, instead of stdout.
+        #[clap(value_parser, short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt::init();
+const CONFIG_TEMPLATE: &str = include_str!("../config.template.yaml");
 
-    let args = Args::parse();
-    let config: Configuration = serde_yaml::from_reader(File::open(&args.config)?)?;
+#[derive(Clone, clap::ValueEnum)]
+enum LogFormat {
+    Text,
+    /// One JSON object per line, for log collectors.
+    Json,
+}
 
-    let sessions: &'static str = "/redfish/v1/SessionService/Sessions";
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    match args.log_format {
+        LogFormat::Text => tracing_subscriber::fmt::init(),
+        LogFormat::Json => tracing_subscriber::fmt().json().init(),
+    }
 
-    let service_root = endpoint::ServiceRoot::new(
-        resource::Name("Basic Redfish Service".to_string()),
-        resource::Id("example-basic".to_string()),
-    )
-    .enable_systems()
-    .enable_sessions(odata_v4::Id(sessions.to_string()));
+    if let Some(command) = args.command {
+        let (output, contents) = match command {
+            Command::GenerateConfig { output } => (output, CONFIG_TEMPLATE.to_string()),
+            Command::MigrateConfig { input, output } => {
+                let mut config = serde_yaml::from_reader(std::fs::File::open(input)?)?;
+                for summary in migration::migrate(&mut config)? {
+                    eprintln!("warning: {}", summary);
+                }
+                (output, serde_yaml::to_string(&config)?)
+            }
+        };
+        match output {
+            Some(output) => std::fs::write(output, contents)?,
+            None => print!("{}", contents),
+        }
+        return Ok(());
+    }
 
-    let service_document = routing::OData::new()
-        .enable_systems()
-        .enable_session_service()
-        .enable_sessions();
+    let config: Configuration =
+        environment::load(args.config.as_deref(), args.data_dir.as_deref())?;
+    if !config.base_path.is_empty() && !config.base_path.starts_with('/') {
+        anyhow::bail!("base-path must start with '/', e.g. /bmc1");
+    }
 
-    let authenticator = LinuxPamAuthenticator::new(config.role_map)?;
-    let session_collection =
-        InMemorySessionManager::new(authenticator.clone(), odata_v4::Id(sessions.to_string()));
-    let proxy = CombinedAuthenticationProxy::new(session_collection.clone(), authenticator);
+    // Sandbox first, so the runtime's worker threads inherit the restrictions.
+    hardening::apply(&config.hardening)?;
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(config))
+}
 
-    let systems = endpoint::Systems::new(
-        odata_v4::Id("/redfish/v1/Systems".to_string()),
-        resource::Name("Computer System Collection".to_string()),
-        vec![endpoint::DummySystem {
-            odata_id: odata_v4::Id("/redfish/v1/Systems/1".to_string()),
-            name: resource::Name("1".to_string()),
-            ..Default::default()
-        }],
-        proxy.clone(),
-    );
+async fn run(mut config: Configuration) -> anyhow::Result<()> {
+    let interop_profile = config.interop_profile.take();
+    let webhooks = std::mem::take(&mut config.webhooks);
+    let mqtt = config.mqtt.take();
+    let dbus = config.dbus;
+    let server = config.server.clone();
+    let Service {
+        app,
+        link,
+        members,
+        events,
+        restrictions,
+    } = service(config).await?;
 
-    let app: Router = Router::new()
-        .route("/redfish", routing::RedfishVersions::default().into())
-        .route(
-            "/redfish/v1",
-            axum::routing::get(|| async { Redirect::permanent("/redfish/v1/") }),
-        )
-        .route(
-            "/redfish/v1/",
-            routing::ServiceRoot::new(service_root).into(),
-        )
-        .route("/redfish/v1/odata", service_document.into())
-        .route("/redfish/v1/$metadata", routing::Metadata.into())
-        .route(
-            "/redfish/v1/Systems",
-            routing::Systems::new(systems.clone()).into(),
-        )
-        .route(
-            "/redfish/v1/Systems/:name",
-            routing::computer_system_detail::ComputerSystemDetail::new(systems.clone()).into(),
-        )
-        .route(
-            "/redfish/v1/Systems/:name/Actions/ComputerSystem.Reset",
-            routing::computer_system_detail::reset::ResetRouter::new(systems).into(),
-        )
-        .route(
-            "/redfish/v1/SessionService",
-            routing::SessionService::new(service::SessionService::new(
-                odata_v4::Id("/redfish/v1/SessionService".to_string()),
-                resource::Name("Stub Session Service".to_string()),
-                odata_v4::Id(sessions.to_string()),
-                proxy.clone(),
-            ))
-            .into(),
-        )
-        .route(
-            sessions,
-            routing::sessions::Sessions::new(service::SessionCollection::new(
-                odata_v4::Id(sessions.to_string()),
-                resource::Name("Session Collection".to_string()),
-                proxy,
-                session_collection.clone(),
-            ))
-            .into(),
-        )
-        .layer(TraceLayer::new_for_http());
+    if let Some(interop_profile) = interop_profile {
+        interop::check(interop_profile, app.clone(), &link.id(links::SERVICE_ROOT).0).await?;
+    }
 
-    redfish_service::serve(config.server, app).await
+    let mut supervisor = supervisor::Supervisor::new();
+    webhook::start(&mut supervisor, webhooks, events.clone());
+    if dbus {
+        dbus::start(&mut supervisor, members, events.clone(), restrictions)?;
+    }
+    if let Some(mqtt) = mqtt {
+        mqtt::start(&mut supervisor, mqtt, events);
+    }
+    supervisor.spawn(
+        "server",
+        supervisor::Restart {
+            max: 3,
+            backoff: Duration::from_secs(1),
+        },
+        true,
+        move || redfish_service::serve(server.clone(), app.clone()),
+    );
+    supervisor.run().await
 }
@@ -0,0 +1,65 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod anonymous;
+pub use anonymous::*;
+
+mod challenge;
+pub use challenge::*;
+
+mod created;
+pub use created::*;
+
+mod credentials;
+pub use credentials::*;
+
+mod etag;
+pub use etag::*;
+
+mod identification;
+pub use identification::*;
+
+mod identifiers;
+pub use identifiers::*;
+
+mod link_header;
+pub use link_header::*;
+
+mod localization;
+pub use localization::*;
+
+mod long_poll;
+pub use long_poll::*;
+
+mod query;
+pub use query::*;
+
+mod password_change;
+pub use password_change::*;
+
+mod read_only;
+pub use read_only::*;
+
+mod recording;
+pub use recording::*;
+
+mod fault_injection;
+pub use fault_injection::*;
+
+#[cfg(feature = "xml")]
+mod xml;
+#[cfg(feature = "xml")]
+pub use xml::*;
@@ -0,0 +1,142 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use redfish_codegen::registries::base::v1_15_0::Base;
+use seuss::{auth::AuthenticateRequest, redfish_error};
+use std::sync::Arc;
+
+use crate::{links, openapi};
+
+/// Resources that DSP0266 requires to be readable without authentication.
+const REQUIRED: [&str; 5] = [
+    links::REDFISH,
+    links::SERVICE_ROOT,
+    "/redfish/v1",
+    links::ODATA,
+    links::METADATA,
+];
+
+/// Resources that are also readable without authentication, so that clients
+/// can learn how to use the service before they log in.
+const DESCRIPTIONS: [&str; 1] = [openapi::PATH];
+
+/// The routes that may be requested without credentials. Everything else
+/// is turned away before it reaches a handler, whatever the handler itself
+/// would check.
+#[derive(Clone)]
+pub struct AnonymousRoutes(Arc<Vec<String>>);
+
+impl AnonymousRoutes {
+    /// The routes required by the specification, the OpenAPI document, and
+    /// `additional` routes. A route ending in `/*` allows the path before it
+    /// and everything below it.
+    pub fn new(additional: Vec<String>) -> Self {
+        let mut routes: Vec<String> = REQUIRED
+            .iter()
+            .chain(DESCRIPTIONS.iter())
+            .map(|path| path.to_string())
+            .collect();
+        routes.extend(additional);
+        AnonymousRoutes(Arc::new(routes))
+    }
+
+    pub fn allows(&self, method: &Method, path: &str) -> bool {
+        // Creating a session is how clients log in.
+        if method == Method::POST && path == links::SESSIONS {
+            return true;
+        }
+        if method != Method::GET && method != Method::HEAD {
+            return false;
+        }
+        self.0.iter().any(|route| match route.strip_suffix("/*") {
+            Some(prefix) => {
+                path == prefix
+                    || (path.starts_with(prefix) && path[prefix.len()..].starts_with('/'))
+            }
+            None => path == route,
+        })
+    }
+}
+
+/// Rejects requests for any route not in `anonymous` unless `authenticator`
+/// accepts their credentials.
+pub async fn require_credentials<A>(
+    State((anonymous, authenticator)): State<(AnonymousRoutes, A)>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response
+where
+    A: AuthenticateRequest,
+{
+    if anonymous.allows(request.method(), request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    match authenticator.authenticate_request(&mut parts) {
+        Ok(Some(_)) => next.run(Request::from_parts(parts, body)).await,
+        Ok(None) => (
+            StatusCode::UNAUTHORIZED,
+            Json(redfish_error::one_message(Base::NoValidSession.into())),
+        )
+            .into_response(),
+        Err(response) => response,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anonymous() -> AnonymousRoutes {
+        AnonymousRoutes::new(vec!["/redfish/v1/Public/*".to_string()])
+    }
+
+    #[test]
+    fn allows_required_routes() {
+        let anonymous = anonymous();
+        for path in REQUIRED.iter().chain(DESCRIPTIONS.iter()) {
+            assert!(anonymous.allows(&Method::GET, path), "{}", path);
+            assert!(anonymous.allows(&Method::HEAD, path), "{}", path);
+        }
+        assert!(anonymous.allows(&Method::POST, links::SESSIONS));
+    }
+
+    #[test]
+    fn allows_subtrees() {
+        let anonymous = anonymous();
+        assert!(anonymous.allows(&Method::GET, "/redfish/v1/Public"));
+        assert!(anonymous.allows(&Method::GET, "/redfish/v1/Public/Status"));
+        assert!(!anonymous.allows(&Method::GET, "/redfish/v1/PublicKeys"));
+    }
+
+    #[test]
+    fn rejects_other_routes_and_methods() {
+        let anonymous = anonymous();
+        assert!(!anonymous.allows(&Method::GET, links::SYSTEMS));
+        assert!(!anonymous.allows(&Method::GET, links::SESSIONS));
+        assert!(!anonymous.allows(&Method::POST, links::SERVICE_ROOT));
+        assert!(!anonymous.allows(&Method::DELETE, "/redfish/v1/Public/Status"));
+    }
+}
@@ -0,0 +1,56 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+/// The challenge sent with 401 responses. Only Basic authentication has a
+/// challenge; clients find the session collection through the service root.
+#[derive(Clone)]
+pub struct Challenge(HeaderValue);
+
+impl Challenge {
+    pub fn new(realm: &str) -> anyhow::Result<Self> {
+        let realm = realm.replace('\\', "\\\\").replace('"', "\\\"");
+        Ok(Challenge(HeaderValue::try_from(format!(
+            "Basic realm=\"{}\"",
+            realm
+        ))?))
+    }
+}
+
+/// Adds a WWW-Authenticate header to 401 responses that lack one, whichever
+/// handler or layer produced them.
+pub async fn challenge(
+    State(challenge): State<Challenge>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let mut response = next.run(request).await;
+    if response.status() == StatusCode::UNAUTHORIZED
+        && !response.headers().contains_key(header::WWW_AUTHENTICATE)
+    {
+        response
+            .headers_mut()
+            .insert(header::WWW_AUTHENTICATE, challenge.0);
+    }
+    response
+}
@@ -0,0 +1,49 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::endpoint::Members;
+
+/// The generated PUT handlers can only answer 200, so this marks a
+/// successful PUT that created its target, as recorded by the endpoint, as
+/// 201 Created, with a Location header pointing at the new system. The id
+/// is the one captured by the route, decoded like the endpoint's.
+pub async fn created_by_put(
+    State(members): State<Members>,
+    Path(id): Path<String>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let put = request.method() == Method::PUT;
+    let mut response = next.run(request).await;
+    if !put || response.status() != StatusCode::OK {
+        return response;
+    }
+    if let Some(odata_id) = members.take_created(&id) {
+        *response.status_mut() = StatusCode::CREATED;
+        if let Ok(location) = HeaderValue::from_str(&odata_id.0) {
+            response.headers_mut().insert(header::LOCATION, location);
+        }
+    }
+    response
+}
@@ -0,0 +1,63 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{auth, links};
+
+/// The credentials of a request to create a session.
+#[derive(serde::Deserialize)]
+struct Login {
+    #[serde(rename = "UserName")]
+    user_name: String,
+    #[serde(rename = "Password")]
+    password: String,
+}
+
+/// Authenticates the credentials of the request on `pool` before it goes on:
+/// the Basic credentials of its Authorization header, and the UserName and
+/// Password of a new session. seuss authenticates synchronously, and is
+/// answered with these verdicts rather than blocking on the backend.
+pub async fn prepare_credentials(
+    State(pool): State<auth::BlockingPoolAuthenticator>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let mut preparations = Vec::new();
+    if let Some((username, password)) = auth::basic_credentials(request.headers()) {
+        preparations.push(pool.prepare(username, password).await);
+    }
+    if request.method() != Method::POST || request.uri().path() != links::SESSIONS {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    if let Ok(login) = serde_json::from_slice::<Login>(&bytes) {
+        preparations.push(pool.prepare(login.user_name, login.password).await);
+    }
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
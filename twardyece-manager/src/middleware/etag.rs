@@ -0,0 +1,194 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::{self, Body, Bytes, Full},
+    http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use redfish_codegen::registries::base::v1_15_0::Base;
+use seuss::redfish_error;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, Weak},
+};
+use tokio::sync::OwnedMutexGuard;
+use tower::ServiceExt;
+
+#[derive(Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct IfMatchConfiguration {
+    /// Resource types, e.g. ComputerSystem, that can only be modified with
+    /// PATCH or PUT when the request carries If-Match.
+    #[serde(rename = "required-for")]
+    required_for: HashSet<String>,
+}
+
+/// A strong ETag derived from the serialized resource. Resources are
+/// serialized deterministically, so equal tags mean byte-identical bodies.
+fn etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let hex: String = digest[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("\"{}\"", hex)
+}
+
+/// A lock for each resource being written, so that If-Match is checked and
+/// the write applied without another write to the resource in between.
+/// Invoking an action is a write to the resource the action belongs to.
+#[derive(Default)]
+struct WriteLocks(Mutex<HashMap<String, Weak<tokio::sync::Mutex<()>>>>);
+
+impl WriteLocks {
+    async fn lock(&self, path: &str) -> OwnedMutexGuard<()> {
+        let resource = path.split("/Actions/").next().unwrap_or(path);
+        let resource = resource.trim_end_matches('/');
+        let lock = {
+            let mut locks = self.0.lock().unwrap();
+            locks.retain(|_, lock| lock.strong_count() > 0);
+            match locks.get(resource).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::new(tokio::sync::Mutex::new(()));
+                    locks.insert(resource.to_string(), Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+        lock.lock_owned().await
+    }
+}
+
+/// State of the [etags] middleware.
+pub struct ETags {
+    config: IfMatchConfiguration,
+    locks: WriteLocks,
+}
+
+impl ETags {
+    pub fn new(config: IfMatchConfiguration) -> Arc<Self> {
+        Arc::new(ETags {
+            config,
+            locks: WriteLocks::default(),
+        })
+    }
+}
+
+/// Adds an ETag for the resource in the body of a successful response.
+async fn tagged(response: Response) -> Response {
+    if !response.status().is_success() || response.headers().contains_key(header::ETAG) {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    if !bytes.is_empty() {
+        if let Ok(value) = HeaderValue::from_str(&etag(&bytes)) {
+            parts.headers.insert(header::ETAG, value);
+        }
+    }
+    Response::from_parts(parts, body::boxed(Full::from(bytes)))
+}
+
+fn error(status: StatusCode, message: Base) -> Response {
+    (status, Json(redfish_error::one_message(message.into()))).into_response()
+}
+
+/// Reads the resource at `uri` through `app`, with the headers of the
+/// original request so that it is read as the same client, returning its
+/// body if the read succeeded.
+async fn current(app: Router, uri: Uri, headers: HeaderMap) -> Option<Bytes> {
+    let mut get = Request::get(uri);
+    for (name, value) in headers.iter() {
+        if name != header::CONTENT_LENGTH
+            && name != header::CONTENT_TYPE
+            && name != header::IF_MATCH
+        {
+            get = get.header(name, value);
+        }
+    }
+    let response = app.oneshot(get.body(Body::empty()).ok()?).await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    hyper::body::to_bytes(response.into_body()).await.ok()
+}
+
+/// Adds an ETag to successful responses to GET, PATCH and PUT, and checks
+/// If-Match on PATCH and PUT against the current representation of the
+/// resource, which is read through `app`. Resource types listed in the
+/// configuration must be modified with If-Match. Writes to each resource are
+/// serialized, so the check and the write are atomic.
+pub async fn etags(
+    app: Router,
+    etags: Arc<ETags>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    if request.method() == Method::GET || request.method() == Method::HEAD {
+        return tagged(next.run(request).await).await;
+    }
+
+    let _lock = etags.locks.lock(request.uri().path()).await;
+    if request.method() != Method::PATCH && request.method() != Method::PUT {
+        return next.run(request).await;
+    }
+
+    // PUT may create the resource, in which case there is nothing to match.
+    let resource = match current(app, request.uri().clone(), request.headers().clone()).await {
+        Some(resource) => resource,
+        None => return tagged(next.run(request).await).await,
+    };
+    let if_match = match request.headers().get(header::IF_MATCH) {
+        Some(if_match) => if_match.to_str().unwrap_or_default(),
+        None => {
+            let schema = serde_json::from_slice::<serde_json::Value>(&resource)
+                .ok()
+                .and_then(|resource| resource.get("@odata.type")?.as_str().map(String::from));
+            let schema = schema.as_deref().map(|schema| {
+                schema
+                    .trim_start_matches('#')
+                    .split('.')
+                    .next()
+                    .unwrap_or_default()
+            });
+            if schema.is_some_and(|schema| etags.config.required_for.contains(schema)) {
+                return error(
+                    StatusCode::PRECONDITION_REQUIRED,
+                    Base::PreconditionRequired,
+                );
+            }
+            return tagged(next.run(request).await).await;
+        }
+    };
+
+    let current = etag(&resource);
+    let matches = if_match
+        .split(',')
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag == current);
+    if !matches {
+        return error(StatusCode::PRECONDITION_FAILED, Base::PreconditionFailed);
+    }
+    tagged(next.run(request).await).await
+}
@@ -0,0 +1,131 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::{Body, Bytes, StreamBody},
+    extract::State,
+    http::{header, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use redfish_codegen::registries::base::v1_15_0::Base;
+use seuss::redfish_error;
+use std::{io, sync::Arc, time::Duration};
+
+#[derive(Clone, serde::Deserialize)]
+pub struct Delay {
+    pub probability: f64,
+    pub milliseconds: u64,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct Failure {
+    pub probability: f64,
+    /// Status code of the response, e.g. 500 or 503.
+    pub status: u16,
+    /// Seconds sent in a Retry-After header, if any.
+    #[serde(rename = "retry-after")]
+    pub retry_after: Option<u64>,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct Disconnect {
+    pub probability: f64,
+}
+
+/// Faults injected into requests whose path starts with `route`, so that the
+/// retry logic of clients can be tested against this service.
+#[derive(Clone, serde::Deserialize)]
+pub struct Fault {
+    pub route: String,
+    pub delay: Option<Delay>,
+    #[serde(rename = "error")]
+    pub failure: Option<Failure>,
+    pub disconnect: Option<Disconnect>,
+}
+
+fn happens(probability: f64) -> bool {
+    rand::random::<f64>() < probability
+}
+
+fn failure_response(failure: &Failure) -> Response {
+    let status = StatusCode::from_u16(failure.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let message = match (status, failure.retry_after) {
+        (StatusCode::SERVICE_UNAVAILABLE, Some(retry_after)) => {
+            Base::ServiceTemporarilyUnavailable(retry_after.to_string())
+        }
+        _ => Base::InternalError,
+    };
+    let mut response = (status, Json(redfish_error::one_message(message.into()))).into_response();
+    if let Some(retry_after) = failure.retry_after {
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    }
+    response
+}
+
+/// A response whose body fails immediately, which makes the server abort the
+/// connection instead of completing the response.
+fn disconnect_response() -> Response {
+    let stream = futures::stream::once(async {
+        Err::<Bytes, _>(io::Error::new(
+            io::ErrorKind::ConnectionAborted,
+            "injected disconnect",
+        ))
+    });
+    StreamBody::new(stream).into_response()
+}
+
+pub async fn inject_faults(
+    State(faults): State<Arc<Vec<Fault>>>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let path = request.uri().path();
+    let fault = match faults
+        .iter()
+        .find(|fault| path.starts_with(fault.route.as_str()))
+    {
+        Some(fault) => fault,
+        None => return next.run(request).await,
+    };
+
+    if let Some(delay) = fault
+        .delay
+        .as_ref()
+        .filter(|delay| happens(delay.probability))
+    {
+        tokio::time::sleep(Duration::from_millis(delay.milliseconds)).await;
+    }
+    if let Some(failure) = fault
+        .failure
+        .as_ref()
+        .filter(|failure| happens(failure.probability))
+    {
+        return failure_response(failure);
+    }
+    if fault
+        .disconnect
+        .as_ref()
+        .is_some_and(|disconnect| happens(disconnect.probability))
+    {
+        return disconnect_response();
+    }
+
+    next.run(request).await
+}
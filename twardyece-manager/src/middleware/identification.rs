@@ -0,0 +1,75 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+const X_MANAGER_INSTANCE: HeaderName = HeaderName::from_static("x-manager-instance");
+
+#[derive(serde::Deserialize)]
+#[serde(default)]
+pub struct IdentificationConfiguration {
+    /// Value of the Server header.
+    server: String,
+    /// Identifies this instance when several managers sit behind one proxy.
+    /// A random one is chosen at startup if absent.
+    instance: Option<uuid::Uuid>,
+}
+
+impl Default for IdentificationConfiguration {
+    fn default() -> Self {
+        IdentificationConfiguration {
+            server: concat!("twardyece-manager/", env!("CARGO_PKG_VERSION")).to_string(),
+            instance: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Identification {
+    server: HeaderValue,
+    instance: HeaderValue,
+}
+
+impl Identification {
+    pub fn new(config: IdentificationConfiguration) -> anyhow::Result<Arc<Self>> {
+        let instance = config.instance.unwrap_or_else(uuid::Uuid::new_v4);
+        tracing::info!("manager instance {}", instance);
+        Ok(Arc::new(Identification {
+            server: HeaderValue::try_from(config.server)?,
+            instance: HeaderValue::try_from(instance.to_string())?,
+        }))
+    }
+}
+
+/// Adds the Server and X-Manager-Instance headers to every response.
+pub async fn identify(
+    State(identification): State<Arc<Identification>>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(header::SERVER, identification.server.clone());
+    headers.insert(X_MANAGER_INSTANCE, identification.instance.clone());
+    response
+}
@@ -0,0 +1,120 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{OriginalUri, State},
+    http::{header, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use redfish_codegen::registries::base::v1_15_0::Base;
+use seuss::redfish_error;
+
+/// Properties that identify a resource, and are assigned by the service.
+const IDENTIFIERS: [&str; 3] = ["@odata.id", "@odata.type", "Id"];
+
+/// What to do with identifying properties supplied by the client on create
+/// or replace.
+#[derive(Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IdentifierPolicy {
+    /// Fail the request with PropertyNotWritable.
+    #[default]
+    Reject,
+    /// Remove the properties from the body and log a warning.
+    Ignore,
+}
+
+/// Whether `value` is what the service itself assigns to `name` for the
+/// resource at `path`. Clients replacing a resource commonly PUT back what
+/// they read, so these are accepted.
+fn is_own(name: &str, value: &serde_json::Value, path: &str) -> bool {
+    let path = path.trim_end_matches('/');
+    match name {
+        "@odata.id" => value.as_str() == Some(path),
+        "Id" => value.as_str() == path.rsplit('/').next(),
+        _ => true,
+    }
+}
+
+/// Applies the policy to @odata.id, @odata.type and Id in the bodies of POST
+/// requests, and to the ones in PUT requests that differ from those of the
+/// resource being replaced.
+pub async fn identifiers(
+    State(policy): State<IdentifierPolicy>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let method = request.method().clone();
+    if method != Method::POST && method != Method::PUT {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    let mut resource = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(serde_json::Value::Object(resource)) => resource,
+        _ => {
+            return next
+                .run(Request::from_parts(parts, Body::from(bytes)))
+                .await
+        }
+    };
+
+    let path = match parts.extensions.get::<OriginalUri>() {
+        Some(OriginalUri(uri)) => uri.path().to_string(),
+        None => parts.uri.path().to_string(),
+    };
+    let supplied: Vec<&str> = IDENTIFIERS
+        .into_iter()
+        .filter(|name| match resource.get(*name) {
+            Some(value) => method == Method::POST || !is_own(name, value, &path),
+            None => false,
+        })
+        .collect();
+    if supplied.is_empty() {
+        return next
+            .run(Request::from_parts(parts, Body::from(bytes)))
+            .await;
+    }
+
+    match policy {
+        IdentifierPolicy::Reject => (
+            StatusCode::BAD_REQUEST,
+            Json(redfish_error::one_message(
+                Base::PropertyNotWritable(supplied[0].to_string()).into(),
+            )),
+        )
+            .into_response(),
+        IdentifierPolicy::Ignore => {
+            tracing::warn!("{} {}: ignoring {}", method, path, supplied.join(", "));
+            for name in supplied {
+                resource.remove(name);
+            }
+            let bytes = Bytes::from(serde_json::Value::Object(resource).to_string());
+            parts
+                .headers
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(bytes.len()));
+            next.run(Request::from_parts(parts, Body::from(bytes)))
+                .await
+        }
+    }
+}
@@ -0,0 +1,63 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::{self, Body, Full},
+    http::{header, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+const SCHEMA_BASE_URI: &str = "http://redfish.dmtf.org/schemas/v1/";
+
+/// Map an `@odata.type` annotation, e.g. `#ComputerSystem.v1_20_0.ComputerSystem`,
+/// to the URI of the JSON Schema that describes it.
+fn schema_uri(odata_type: &str) -> Option<String> {
+    let (namespace, _) = odata_type.trim_start_matches('#').rsplit_once('.')?;
+    Some(SCHEMA_BASE_URI.to_string() + namespace + ".json")
+}
+
+/// Adds a `Link` header with `rel=describedby` to GET responses for Redfish
+/// resources, pointing at the JSON Schema named by the `@odata.type` of the
+/// response body.
+pub async fn describedby(request: Request<Body>, next: Next<Body>) -> Response {
+    let is_get = request.method() == Method::GET;
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !is_get || !is_json || !response.status().is_success() {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    let link = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .ok()
+        .and_then(|value| value.get("@odata.type")?.as_str().and_then(schema_uri))
+        .and_then(|uri| HeaderValue::try_from(format!("<{}>; rel=describedby", uri)).ok());
+    if let Some(link) = link {
+        parts.headers.append(header::LINK, link);
+    }
+
+    Response::from_parts(parts, body::boxed(Full::from(bytes)))
+}
@@ -0,0 +1,208 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use axum::{
+    body::{self, Body, Full},
+    extract::State,
+    http::{header, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+use std::{collections::HashMap, fs::File, path::PathBuf, sync::Arc};
+
+#[derive(Clone, serde::Deserialize)]
+pub struct LocalizationConfiguration {
+    /// Directory of translated message registries, in the DMTF registry
+    /// JSON format, e.g. Base.1.15.0.de.json. Each registry's Language
+    /// property determines which Accept-Language it is served for.
+    pub directory: PathBuf,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct MessageRegistry {
+    language: String,
+    registry_prefix: String,
+    messages: HashMap<String, RegistryMessage>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RegistryMessage {
+    message: String,
+}
+
+/// Message templates of one language, keyed by registry prefix and message
+/// key.
+type Messages = HashMap<(String, String), String>;
+
+/// Message templates, keyed by language, then by registry prefix and
+/// message key, e.g. "de" -> ("Base", "PropertyMissing").
+#[derive(Clone)]
+pub struct Localization {
+    languages: Arc<HashMap<String, Messages>>,
+}
+
+impl Localization {
+    pub fn new(config: LocalizationConfiguration) -> anyhow::Result<Self> {
+        let mut languages: HashMap<String, Messages> = HashMap::new();
+        for entry in std::fs::read_dir(&config.directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let file = File::open(&path)?;
+            let registry: MessageRegistry = serde_json::from_reader(file)
+                .with_context(|| format!("failed to parse {}", path.display()))?;
+            let messages = languages
+                .entry(registry.language.to_lowercase())
+                .or_default();
+            for (key, message) in registry.messages {
+                messages.insert((registry.registry_prefix.clone(), key), message.message);
+            }
+        }
+        Ok(Localization {
+            languages: Arc::new(languages),
+        })
+    }
+
+    /// Picks the language to respond in from an Accept-Language header,
+    /// preferring higher quality values. A range such as de-AT also matches
+    /// registries for de. Ranges with a quality of 0 are not acceptable.
+    fn negotiate(&self, accept_language: &str) -> Option<&str> {
+        let mut ranges: Vec<(&str, f32)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|parameter| parameter.trim().strip_prefix("q="))
+                    .and_then(|quality| quality.parse().ok())
+                    .unwrap_or(1.0);
+                (quality > 0.0).then_some((tag, quality))
+            })
+            .collect();
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        for (tag, _) in ranges {
+            let tag = tag.to_lowercase();
+            let primary = tag.split('-').next().unwrap_or_default().to_string();
+            // English is what the service already speaks.
+            if primary == "en" {
+                return None;
+            }
+            for candidate in [tag, primary] {
+                if let Some((language, _)) = self.languages.get_key_value(&candidate) {
+                    return Some(language);
+                }
+            }
+        }
+        None
+    }
+
+    fn translate(&self, language: &str, value: &mut Value) -> bool {
+        let messages = match self.languages.get(language) {
+            Some(messages) => messages,
+            None => return false,
+        };
+        let mut translated = false;
+        match value {
+            Value::Object(object) => {
+                let template = object
+                    .get("MessageId")
+                    .and_then(|id| id.as_str())
+                    .and_then(|id| {
+                        // MessageIds look like Base.1.15.0.PropertyMissing
+                        let (prefix, rest) = id.split_once('.')?;
+                        let key = rest.rsplit('.').next()?;
+                        messages.get(&(prefix.to_string(), key.to_string()))
+                    });
+                if let Some(template) = template {
+                    let arguments = object
+                        .get("MessageArgs")
+                        .and_then(|arguments| arguments.as_array())
+                        .cloned()
+                        .unwrap_or_default();
+                    // Substitute from the highest index down, so that %1 is
+                    // not replaced inside %10.
+                    let mut message = template.clone();
+                    for (index, argument) in arguments.iter().enumerate().rev() {
+                        let argument = match argument {
+                            Value::String(argument) => argument.clone(),
+                            argument => argument.to_string(),
+                        };
+                        message = message.replace(&format!("%{}", index + 1), &argument);
+                    }
+                    object.insert("Message".to_string(), Value::String(message));
+                    translated = true;
+                }
+                for value in object.values_mut() {
+                    translated |= self.translate(language, value);
+                }
+            }
+            Value::Array(array) => {
+                for value in array {
+                    translated |= self.translate(language, value);
+                }
+            }
+            _ => {}
+        }
+        translated
+    }
+}
+
+/// Rewrites the text of messages in responses to the language requested by
+/// the client, when a translated registry for it is available. Responses in
+/// any other case are passed through in English.
+pub async fn localize(
+    State(localization): State<Localization>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let language = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| localization.negotiate(value))
+        .map(|language| language.to_string());
+    let response = next.run(request).await;
+    let language = match language {
+        Some(language) => language,
+        None => return response,
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let mut value: Value = match serde_json::from_slice(&bytes) {
+        Ok(value) => value,
+        Err(_) => return Response::from_parts(parts, body::boxed(Full::from(bytes))),
+    };
+    if !localization.translate(&language, &mut value) {
+        return Response::from_parts(parts, body::boxed(Full::from(bytes)));
+    }
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    if let Ok(language) = HeaderValue::from_str(&language) {
+        parts.headers.insert(header::CONTENT_LANGUAGE, language);
+    }
+    let body = serde_json::to_vec(&value).unwrap_or_default();
+    Response::from_parts(parts, body::boxed(Full::from(body)))
+}
@@ -0,0 +1,131 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::Body,
+    extract::Query,
+    http::{header, Method, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Router,
+};
+use std::time::Duration;
+use tokio::{sync::watch, time::Instant};
+use tower::ServiceExt;
+
+use crate::events::Events;
+
+/// Clients may not hold a request open for longer than this.
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+#[derive(serde::Deserialize)]
+struct Parameters {
+    /// Seconds to hold the request open while the resource is unchanged.
+    wait: Option<u64>,
+    /// ETag of the representation the client already has.
+    etag: Option<String>,
+}
+
+/// Wakes requests waiting for a change: on every event, and after every
+/// successful modifying request, since not every change is an event.
+#[derive(Clone)]
+pub struct LongPoll {
+    events: Events,
+    writes: watch::Sender<u64>,
+}
+
+impl LongPoll {
+    pub fn new(events: Events) -> Self {
+        LongPoll {
+            events,
+            writes: watch::channel(0).0,
+        }
+    }
+}
+
+/// ETags compare equal regardless of whether the client kept the weak
+/// prefix and the quotes.
+fn opaque(etag: &str) -> &str {
+    etag.trim().trim_start_matches("W/").trim_matches('"')
+}
+
+fn is_current(response: &Response, etag: &str) -> bool {
+    response
+        .headers()
+        .get(header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|current| opaque(current) == opaque(etag))
+}
+
+/// Whether a successful request with `method` may have changed a resource.
+fn is_write(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    )
+}
+
+/// OEM long polling: a GET with `?wait=<seconds>&etag=<value>` is held open
+/// until the ETag of the resource differs from `etag`, or `wait` seconds
+/// have passed, and then answered with the resource as usual. The resource
+/// is read again through `app` whenever something may have changed it.
+pub async fn long_poll(
+    app: Router,
+    long_poll: LongPoll,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    if is_write(request.method()) {
+        let response = next.run(request).await;
+        if response.status().is_success() {
+            long_poll.writes.send_modify(|writes| *writes += 1);
+        }
+        return response;
+    }
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+
+    let (wait, etag) = match Query::<Parameters>::try_from_uri(request.uri()) {
+        Ok(Query(Parameters {
+            wait: Some(wait),
+            etag: Some(etag),
+        })) => (Duration::from_secs(wait).min(MAX_WAIT), etag),
+        _ => return next.run(request).await,
+    };
+    let deadline = Instant::now() + wait;
+    let (uri, headers) = (request.uri().clone(), request.headers().clone());
+
+    // Subscribe before the first read, so that no change is missed.
+    let mut events = long_poll.events.subscribe();
+    let mut writes = long_poll.writes.subscribe();
+    let mut response = next.run(request).await;
+    while response.status().is_success() && is_current(&response, &etag) {
+        let changed = tokio::select! {
+            _ = events.recv() => true,
+            result = writes.changed() => result.is_ok(),
+            _ = tokio::time::sleep_until(deadline) => false,
+        };
+        if !changed {
+            break;
+        }
+        let mut request = Request::new(Body::empty());
+        *request.uri_mut() = uri.clone();
+        *request.headers_mut() = headers.clone();
+        response = app.clone().oneshot(request).await.into_response();
+    }
+    response
+}
@@ -0,0 +1,75 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use redfish_codegen::registries::base::v1_15_0::Base;
+use seuss::{auth::AuthenticateRequest, redfish_error};
+
+use crate::{
+    auth::PasswordFileAuthenticator,
+    links::{self, LinkBuilder},
+};
+
+/// Whether a user who must change their password may still make `request`:
+/// reading and changing their own account, and logging in and out.
+fn is_allowed(request: &Request<Body>, account: &str) -> bool {
+    let path = request.uri().path();
+    match *request.method() {
+        Method::GET | Method::PATCH => path == account,
+        Method::POST => path == links::SESSIONS,
+        Method::DELETE => path
+            .strip_prefix(links::SESSIONS)
+            .is_some_and(|member| member.starts_with('/')),
+        _ => false,
+    }
+}
+
+/// Answers every request of a user whose account in `accounts` is marked
+/// PasswordChangeRequired with 403 and a PasswordChangeRequired message
+/// pointing at the account, until they change their password.
+pub async fn require_password_change<A>(
+    State((accounts, authenticator, link)): State<(PasswordFileAuthenticator, A, LinkBuilder)>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response
+where
+    A: AuthenticateRequest,
+{
+    let (mut parts, body) = request.into_parts();
+    let username = match authenticator.authenticate_request(&mut parts) {
+        Ok(Some(user)) if accounts.password_change_required(&user.username) => user.username,
+        _ => return next.run(Request::from_parts(parts, body)).await,
+    };
+    let request = Request::from_parts(parts, body);
+    let account = format!("{}/{}", links::ACCOUNTS, username);
+    if is_allowed(&request, &account) {
+        return next.run(request).await;
+    }
+
+    let message = Base::PasswordChangeRequired(link.id(&account).0);
+    (
+        StatusCode::FORBIDDEN,
+        Json(redfish_error::one_message(message.into())),
+    )
+        .into_response()
+}
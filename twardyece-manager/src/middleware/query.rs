@@ -0,0 +1,121 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::{self, Body, Full},
+    http::{header, Method, Request, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use redfish_codegen::registries::base::v1_15_0::Base;
+use seuss::redfish_error;
+use tower::ServiceExt;
+
+use crate::links::LinkBuilder;
+
+/// Query parameters defined by Redfish that do not start with `$`.
+const REDFISH_PARAMETERS: [&str; 3] = ["only", "excerpt", "includeoriginresource"];
+
+fn error(status: StatusCode, message: Base) -> Response {
+    (status, Json(redfish_error::one_message(message.into()))).into_response()
+}
+
+fn without_query(uri: &Uri) -> Uri {
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = parts
+        .path_and_query
+        .and_then(|path_and_query| path_and_query.path().parse().ok());
+    Uri::from_parts(parts).unwrap_or_else(|_| uri.clone())
+}
+
+/// Rejects the Redfish query parameters this service does not implement,
+/// instead of silently ignoring them, and implements `only`: a GET on a
+/// collection with exactly one member returns that member. Query parameters
+/// that are not defined by Redfish are ignored, as the specification requires.
+/// Requests for the single member are dispatched through `app`, which is
+/// mounted under the base path of `link`.
+pub async fn query_parameters(
+    app: Router,
+    link: LinkBuilder,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let parameters: Vec<&str> = request
+        .uri()
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .map(|pair| pair.split('=').next().unwrap_or_default())
+        .filter(|name| name.starts_with('$') || REDFISH_PARAMETERS.contains(name))
+        .collect();
+    if parameters.is_empty() {
+        return next.run(request).await;
+    }
+    if parameters.contains(&"only") && parameters.len() > 1 {
+        return error(StatusCode::BAD_REQUEST, Base::QueryCombinationInvalid);
+    }
+    if parameters.iter().any(|name| *name != "only") {
+        return error(StatusCode::NOT_IMPLEMENTED, Base::QueryNotSupported);
+    }
+    if request.method() != Method::GET {
+        return error(StatusCode::BAD_REQUEST, Base::QueryNotSupportedOnOperation);
+    }
+
+    let headers = request.headers().clone();
+    let (mut parts, body) = request.into_parts();
+    parts.uri = without_query(&parts.uri);
+    let response = next.run(Request::from_parts(parts, body)).await;
+    if !response.status().is_success() {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let collection: serde_json::Value = serde_json::from_slice(&bytes).unwrap_or_default();
+    let members = match collection
+        .get("Members")
+        .and_then(|members| members.as_array())
+    {
+        Some(members) => members,
+        None => return error(StatusCode::BAD_REQUEST, Base::QueryNotSupportedOnResource),
+    };
+
+    // Collections with any other number of members are returned as they are.
+    let member = match members.as_slice() {
+        [member] => member.get("@odata.id").and_then(|id| id.as_str()),
+        _ => None,
+    };
+    let member = match member {
+        Some(member) => member,
+        None => return Response::from_parts(parts, body::boxed(Full::from(bytes))),
+    };
+
+    let member = member.strip_prefix(link.base_path()).unwrap_or(member);
+    let mut request = Request::get(member);
+    for (name, value) in headers.iter() {
+        if name != header::CONTENT_LENGTH {
+            request = request.header(name, value);
+        }
+    }
+    match request.body(Body::empty()) {
+        Ok(request) => app.oneshot(request).await.into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
@@ -0,0 +1,60 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::Body,
+    http::{header, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use redfish_codegen::registries::base::v1_15_0::Base;
+use seuss::redfish_error;
+
+use crate::links;
+
+/// Logging in and out doesn't modify the service, and clients need a session
+/// even to read it.
+fn is_session_request(request: &Request<Body>) -> bool {
+    let path = request.uri().path();
+    match *request.method() {
+        Method::POST => path == links::SESSIONS,
+        Method::DELETE => path
+            .strip_prefix(links::SESSIONS)
+            .and_then(|member| member.strip_prefix('/'))
+            .is_some_and(|id| !id.is_empty() && !id.contains('/')),
+        _ => false,
+    }
+}
+
+/// Rejects every request that could modify the service, for maintenance
+/// freezes and demo deployments, with 405 OperationNotAllowed. GET and HEAD
+/// are unaffected, and so are creating and deleting sessions.
+pub async fn read_only(request: Request<Body>, next: Next<Body>) -> Response {
+    if request.method() == Method::GET
+        || request.method() == Method::HEAD
+        || is_session_request(&request)
+    {
+        return next.run(request).await;
+    }
+
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        [(header::ALLOW, HeaderValue::from_static("GET, HEAD"))],
+        Json(redfish_error::one_message(Base::OperationNotAllowed.into())),
+    )
+        .into_response()
+}
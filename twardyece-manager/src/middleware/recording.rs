@@ -0,0 +1,166 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::{self, Body, Bytes, Full},
+    extract::State,
+    http::{HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+const REDACTED: &str = "<redacted>";
+const SENSITIVE_HEADERS: [&str; 4] = ["authorization", "x-auth-token", "cookie", "set-cookie"];
+
+#[derive(Clone, serde::Deserialize)]
+pub struct RecordingConfiguration {
+    /// Directory the recordings are written to.
+    pub directory: PathBuf,
+    /// Requests whose path starts with one of these prefixes are recorded.
+    pub routes: Vec<String>,
+    /// Number of recordings kept. Once reached, the oldest is overwritten.
+    #[serde(default = "RecordingConfiguration::default_limit")]
+    pub limit: u64,
+}
+
+impl RecordingConfiguration {
+    fn default_limit() -> u64 {
+        100
+    }
+}
+
+/// Records request/response pairs to disk for debugging interoperability
+/// problems. Credentials are stripped before anything is written.
+#[derive(Clone)]
+pub struct Recorder {
+    config: Arc<RecordingConfiguration>,
+    sequence: Arc<AtomicU64>,
+}
+
+impl Recorder {
+    pub fn new(config: RecordingConfiguration) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(&config.directory)?;
+        Ok(Recorder {
+            config: Arc::new(config),
+            sequence: Arc::new(AtomicU64::new(0)),
+        })
+    }
+}
+
+fn sanitize_headers(headers: &HeaderMap) -> serde_json::Value {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).to_string()
+            };
+            (name.to_string(), serde_json::Value::String(value))
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+fn sanitize_value(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key.to_lowercase().contains("password") {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    sanitize_value(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(sanitize_value),
+        _ => {}
+    }
+}
+
+fn sanitize_body(bytes: &Bytes) -> serde_json::Value {
+    if bytes.is_empty() {
+        return serde_json::Value::Null;
+    }
+    match serde_json::from_slice(bytes) {
+        Ok(mut value) => {
+            sanitize_value(&mut value);
+            value
+        }
+        Err(_) => serde_json::Value::String(String::from_utf8_lossy(bytes).to_string()),
+    }
+}
+
+pub async fn record(
+    State(recorder): State<Recorder>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let path = request.uri().path();
+    if !recorder
+        .config
+        .routes
+        .iter()
+        .any(|route| path.starts_with(route.as_str()))
+    {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let request_body = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    let sequence = recorder.sequence.fetch_add(1, Ordering::Relaxed);
+    let mut recording = serde_json::json!({
+        "sequence": sequence,
+        "request": {
+            "method": parts.method.as_str(),
+            "uri": parts.uri.to_string(),
+            "headers": sanitize_headers(&parts.headers),
+            "body": sanitize_body(&request_body),
+        },
+    });
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(request_body)))
+        .await;
+    let (parts, body) = response.into_parts();
+    let response_body = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    recording["response"] = serde_json::json!({
+        "status": parts.status.as_u16(),
+        "headers": sanitize_headers(&parts.headers),
+        "body": sanitize_body(&response_body),
+    });
+
+    let slot = sequence % recorder.config.limit.max(1);
+    let file = recorder.config.directory.join(format!("{}.json", slot));
+    if let Err(error) = tokio::fs::write(&file, recording.to_string()).await {
+        tracing::warn!("failed to write recording {}: {}", file.display(), error);
+    }
+
+    Response::from_parts(parts, body::boxed(Full::from(response_body)))
+}
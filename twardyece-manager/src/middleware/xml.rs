@@ -0,0 +1,109 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::{self, Body, Full},
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+
+/// The quality the client gave to `media_type` in Accept, or zero if it
+/// did not list it.
+fn quality(headers: &HeaderMap, media_type: &str) -> f32 {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            if !parts.next()?.trim().eq_ignore_ascii_case(media_type) {
+                return None;
+            }
+            Some(
+                parts
+                    .find_map(|parameter| parameter.trim().strip_prefix("q="))
+                    .and_then(|quality| quality.parse().ok())
+                    .unwrap_or(1.0),
+            )
+        })
+        .fold(0.0, f32::max)
+}
+
+/// Annotations such as @Message.ExtendedInfo are not valid element names,
+/// so they are written without the leading '@'.
+fn element_names(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| {
+                    (
+                        key.trim_start_matches('@').to_string(),
+                        element_names(value),
+                    )
+                })
+                .collect(),
+        ),
+        Value::Array(array) => Value::Array(array.into_iter().map(element_names).collect()),
+        value => value,
+    }
+}
+
+fn to_xml(bytes: &[u8]) -> Option<String> {
+    let mut value: Value = serde_json::from_slice(bytes).ok()?;
+    let error = element_names(value.get_mut("error")?.take());
+    let document = quick_xml::se::to_string_with_root("error", &error).ok()?;
+    Some(r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string() + &document)
+}
+
+/// Renders Redfish error responses as XML for clients that prefer
+/// application/xml to application/json in Accept, for legacy tooling that
+/// cannot parse JSON. Successful responses are always JSON.
+pub async fn xml_errors(request: Request<Body>, next: Next<Body>) -> Response {
+    let xml = quality(request.headers(), "application/xml")
+        > quality(request.headers(), "application/json");
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !xml
+        || !is_json
+        || !(response.status().is_client_error() || response.status().is_server_error())
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let document = match to_xml(&bytes) {
+        Some(document) => document,
+        None => return Response::from_parts(parts, body::boxed(Full::from(bytes))),
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/xml; charset=utf-8"),
+    );
+    Response::from_parts(parts, body::boxed(Full::from(document)))
+}
@@ -0,0 +1,73 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde_yaml::{Mapping, Value};
+
+/// The configuration schema this build understands.
+pub const CURRENT_VERSION: u64 = 1;
+
+/// Upgrades a configuration from version `from` to `from + 1`.
+struct Migration {
+    from: u64,
+    /// What changed, reported when the migration is applied.
+    summary: &'static str,
+    apply: fn(&mut Mapping),
+}
+
+/// Configurations written before the `version` key was introduced are
+/// version 0. Their schema is identical to version 1.
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    summary: "configuration has no version; it is assumed to be version 1",
+    apply: |_| {},
+}];
+
+/// Upgrades `config`, as read from a configuration file, to
+/// [CURRENT_VERSION] in memory, returning a summary of every migration
+/// applied. Fails for versions newer than this build understands.
+pub fn migrate(config: &mut Value) -> anyhow::Result<Vec<&'static str>> {
+    let config = match config {
+        Value::Mapping(config) => config,
+        Value::Null => return Ok(Vec::new()),
+        _ => anyhow::bail!("configuration must be a mapping"),
+    };
+    let mut version = match config.get("version") {
+        Some(version) => match version.as_u64() {
+            Some(version) => version,
+            None => anyhow::bail!("version must be a non-negative integer"),
+        },
+        None => 0,
+    };
+    if version > CURRENT_VERSION {
+        anyhow::bail!(
+            "configuration version {} is newer than the supported version {}",
+            version,
+            CURRENT_VERSION
+        );
+    }
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS {
+        if migration.from < version {
+            continue;
+        }
+        applied.push(migration.summary);
+        (migration.apply)(config);
+        version = migration.from + 1;
+    }
+    config.insert("version".into(), version.into());
+    Ok(applied)
+}
@@ -0,0 +1,116 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rumqttc::{AsyncClient, MqttOptions, QoS};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    events::Events,
+    supervisor::{Restart, Supervisor},
+};
+
+/// Publishes that may be waiting for the connection to the broker.
+const CAPACITY: usize = 64;
+
+/// Publishes events to an MQTT broker, as JSON objects holding every property
+/// of the event.
+#[derive(serde::Deserialize)]
+pub struct Configuration {
+    pub host: String,
+    #[serde(default = "Configuration::default_port")]
+    pub port: u16,
+    #[serde(rename = "client-id", default = "Configuration::default_client_id")]
+    pub client_id: String,
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: String,
+    /// Topic each event is published to. Occurrences of `{name}` are
+    /// replaced by the property `name` of the event, e.g. `{system}`.
+    #[serde(default = "Configuration::default_topic")]
+    pub topic: String,
+    /// Have the broker keep the last event of each topic for new subscribers,
+    /// e.g. so dashboards show the current power state at once.
+    #[serde(default)]
+    pub retain: bool,
+}
+
+impl Configuration {
+    fn default_port() -> u16 {
+        1883
+    }
+
+    fn default_client_id() -> String {
+        "twardyece-manager".to_string()
+    }
+
+    fn default_topic() -> String {
+        "twardyece-manager/{system}/{event}".to_string()
+    }
+}
+
+async fn bridge(config: Arc<Configuration>, events: Events) -> anyhow::Result<()> {
+    let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let Some(username) = &config.username {
+        options.set_credentials(username, &config.password);
+    }
+    let (client, mut connection) = AsyncClient::new(options, CAPACITY);
+    let mut events = events.subscribe();
+    loop {
+        tokio::select! {
+            // The connection only makes progress, and reconnects, while it is
+            // polled.
+            notification = connection.poll() => {
+                if let Err(error) = notification {
+                    tracing::warn!("MQTT connection to {} failed: {}", config.host, error);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!("MQTT bridge missed {} events", missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                };
+                let topic = event.format(&config.topic);
+                let payload = event.to_json().to_string();
+                if let Err(error) =
+                    client.try_publish(topic, QoS::AtLeastOnce, config.retain, payload)
+                {
+                    tracing::warn!("dropped MQTT publish: {}", error);
+                }
+            }
+        }
+    }
+}
+
+/// Publishes the events published to `events` to the configured broker.
+pub fn start(supervisor: &mut Supervisor, config: Configuration, events: Events) {
+    let config = Arc::new(config);
+    supervisor.spawn(
+        "mqtt",
+        Restart {
+            max: 3,
+            backoff: Duration::from_secs(1),
+        },
+        false,
+        move || bridge(config.clone(), events.clone()),
+    );
+}
@@ -0,0 +1,297 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    http::{header, Method, StatusCode},
+    response::IntoResponse,
+    routing::{get, MethodRouter},
+    Router,
+};
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+
+use crate::{links::LinkBuilder, middleware::AnonymousRoutes, registry::Registry};
+
+/// Where the document is served, as recommended by DSP0266.
+pub const PATH: &str = "/redfish/v1/openapi.yaml";
+
+const SCHEMAS: &str = "http://redfish.dmtf.org/schemas/v1/";
+const ERROR_SCHEMA: &str = "RedfishError.v1_0_2.yaml#/components/schemas/RedfishError";
+
+/// The description of a route in the document. `schema` and `request` are
+/// references into the DMTF OpenAPI schemas for the resource and the request
+/// body.
+#[derive(Clone, Copy)]
+pub struct Route {
+    methods: &'static [Method],
+    schema: Option<&'static str>,
+    request: Option<&'static str>,
+    /// Mounted outside the authentication layers, so that no credentials are
+    /// required whatever the anonymous routes are.
+    public: bool,
+}
+
+impl Route {
+    pub const fn new(methods: &'static [Method]) -> Self {
+        Route {
+            methods,
+            schema: None,
+            request: None,
+            public: false,
+        }
+    }
+
+    pub const fn schema(mut self, schema: &'static str) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    pub const fn request(mut self, request: &'static str) -> Self {
+        self.request = Some(request);
+        self
+    }
+
+    pub const fn public(mut self) -> Self {
+        self.public = true;
+        self
+    }
+}
+
+const SYSTEM_SCHEMA: &str =
+    "ComputerSystem.v1_20_0.yaml#/components/schemas/ComputerSystem_v1_20_0_ComputerSystem";
+
+/// A resource that is only read, and has no schema of its own.
+pub const GET: Route = Route::new(&[Method::GET]);
+/// An action, or any other target that is only posted to.
+pub const POST: Route = Route::new(&[Method::POST]);
+pub const SERVICE_ROOT: Route =
+    GET.schema("ServiceRoot.yaml#/components/schemas/ServiceRoot_ServiceRoot");
+pub const SYSTEMS: Route = Route::new(&[Method::GET, Method::POST])
+    .schema(
+        "ComputerSystemCollection.yaml#/components/schemas/ComputerSystemCollection_ComputerSystemCollection",
+    )
+    .request(SYSTEM_SCHEMA);
+pub const SYSTEM: Route = Route::new(&[Method::GET, Method::PUT, Method::PATCH, Method::DELETE])
+    .schema(SYSTEM_SCHEMA)
+    .request(SYSTEM_SCHEMA);
+pub const RESET: Route = POST.request(
+    "ComputerSystem.v1_20_0.yaml#/components/schemas/ComputerSystem_v1_20_0_ResetRequestBody",
+);
+pub const SESSION_SERVICE: Route =
+    GET.schema("SessionService.yaml#/components/schemas/SessionService_SessionService");
+pub const SESSIONS: Route = Route::new(&[Method::GET, Method::POST])
+    .schema("SessionCollection.yaml#/components/schemas/SessionCollection_SessionCollection")
+    .request("Session.yaml#/components/schemas/Session_Session");
+pub const SESSION: Route = Route::new(&[Method::GET, Method::DELETE])
+    .schema("Session.yaml#/components/schemas/Session_Session");
+pub const ACCOUNT: Route = Route::new(&[Method::GET, Method::PATCH])
+    .schema("ManagerAccount.yaml#/components/schemas/ManagerAccount_ManagerAccount")
+    .request("ManagerAccount.yaml#/components/schemas/ManagerAccount_ManagerAccount");
+/// A resource that is read and deleted, and has no schema of its own.
+pub const GET_DELETE: Route = Route::new(&[Method::GET, Method::DELETE]);
+
+/// Mounts routes and records their description at the same time, so that the
+/// document describes exactly the routes that are served.
+#[derive(Default)]
+pub struct Routes {
+    router: Router,
+    routes: Vec<(String, Route)>,
+}
+
+impl Routes {
+    pub fn route(mut self, path: &str, method_router: MethodRouter, route: Route) -> Self {
+        self.router = self.router.route(path, method_router);
+        self.describe(path, route)
+    }
+
+    /// Describes a route that is served by a middleware, rather than by a
+    /// handler on the router.
+    pub fn describe(mut self, path: &str, route: Route) -> Self {
+        self.routes.push((path.to_string(), route));
+        self
+    }
+
+    pub fn merge(mut self, other: Routes) -> Self {
+        self.router = self.router.merge(other.router);
+        self.routes.extend(other.routes);
+        self
+    }
+
+    /// Describes the routes of `other` without mounting them, for routes that
+    /// are mounted outside the layers of this router.
+    pub fn describe_all(mut self, other: &Routes) -> Self {
+        self.routes.extend(other.routes.iter().cloned());
+        self
+    }
+
+    /// The mounted paths, as given to [Router::route].
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.routes.iter().map(|(path, _)| path.as_str())
+    }
+
+    /// Splits into the router and the description of each of its routes.
+    pub fn into_parts(self) -> (Router, Vec<(String, Route)>) {
+        (self.router, self.routes)
+    }
+
+    pub fn into_router(self) -> Router {
+        self.router
+    }
+
+    /// Mounts the document at [PATH], describing every route recorded so
+    /// far.
+    pub fn serve_document(
+        self,
+        link: LinkBuilder,
+        anonymous: AnonymousRoutes,
+        registry: Registry,
+    ) -> Self {
+        let mut routes = self.describe(PATH, GET);
+        let document = Arc::new(Document {
+            routes: routes.routes.clone(),
+            link,
+            anonymous,
+            registry,
+        });
+        routes.router = routes.router.route(
+            PATH,
+            get(move || async move {
+                match serde_yaml::to_string(&document.render()) {
+                    Ok(document) => {
+                        ([(header::CONTENT_TYPE, "application/yaml")], document).into_response()
+                    }
+                    Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+                }
+            }),
+        );
+        routes
+    }
+}
+
+fn reference(schema: &str) -> Value {
+    json!({ "$ref": String::from(SCHEMAS) + schema })
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+/// The path of a route in the document, with the captures of the route as
+/// path parameters.
+fn template(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix([':', '*']) {
+            Some(name) => format!("{{{}}}", name),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn operation(path: &str, route: &Route, method: &Method, public: bool) -> Value {
+    let mut operation = Map::new();
+    let parameters: Vec<Value> = path
+        .split('/')
+        .filter_map(|segment| segment.strip_prefix([':', '*']))
+        .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }))
+        .collect();
+    if !parameters.is_empty() {
+        operation.insert("parameters".into(), parameters.into());
+    }
+    if let (&Method::POST | &Method::PUT | &Method::PATCH, Some(request)) = (method, route.request)
+    {
+        // PATCH bodies hold only the properties being changed.
+        let schema = match *method {
+            Method::PATCH => json!({ "type": "object" }),
+            _ => reference(request),
+        };
+        operation.insert(
+            "requestBody".into(),
+            json!({ "required": true, "content": json_content(schema) }),
+        );
+    }
+
+    let success = match (method, route.schema) {
+        (&Method::DELETE, _) => json!({ "description": "Deleted" }),
+        (&Method::GET | &Method::PUT | &Method::PATCH, Some(schema)) => {
+            json!({ "description": "Success", "content": json_content(reference(schema)) })
+        }
+        _ => json!({ "description": "Success" }),
+    };
+    operation.insert(
+        "responses".into(),
+        json!({
+            "200": success,
+            "default": {
+                "description": "Error",
+                "content": json_content(reference(ERROR_SCHEMA)),
+            },
+        }),
+    );
+    if public {
+        operation.insert("security".into(), json!([]));
+    }
+    operation.into()
+}
+
+struct Document {
+    routes: Vec<(String, Route)>,
+    link: LinkBuilder,
+    anonymous: AnonymousRoutes,
+    registry: Registry,
+}
+
+impl Document {
+    /// An OpenAPI 3.1 description of the routes served below the base path,
+    /// including the resources registered at runtime.
+    fn render(&self) -> Value {
+        let mut paths = Map::new();
+        for (path, route) in self.routes.iter() {
+            let operations: Map<String, Value> = route
+                .methods
+                .iter()
+                .map(|method| {
+                    let public = route.public || self.anonymous.allows(method, path);
+                    let operation = operation(path, route, method, public);
+                    (method.as_str().to_lowercase(), operation)
+                })
+                .collect();
+            paths.insert(self.link.id(&template(path)).0, operations.into());
+        }
+        for prefix in self.registry.prefixes() {
+            paths.insert(
+                self.link.id(&prefix).0,
+                json!({ "get": { "responses": { "200": { "description": "Success" } } } }),
+            );
+        }
+
+        json!({
+            "openapi": "3.1.0",
+            "info": {
+                "title": "twardyece-manager",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "paths": paths,
+            "components": {
+                "securitySchemes": {
+                    "basic": { "type": "http", "scheme": "basic" },
+                    "session": { "type": "apiKey", "in": "header", "name": "X-Auth-Token" },
+                },
+            },
+            "security": [{ "basic": [] }, { "session": [] }],
+        })
+    }
+}
@@ -0,0 +1,181 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use redfish_codegen::{
+    models::resource::{PowerState, ResetType},
+    registries::base::v1_15_0::Base,
+};
+
+use crate::action::{ActionInfo, Parameter};
+
+/// ComputerSystem.Reset, with the ResetTypes that [reset] can apply.
+pub const RESET_ACTION_INFO: ActionInfo = ActionInfo {
+    id: "ResetActionInfo",
+    name: "Reset Action Info",
+    parameters: &[Parameter {
+        name: "ResetType",
+        required: true,
+        data_type: "String",
+        allowable_values: &[
+            "On",
+            "ForceOff",
+            "GracefulShutdown",
+            "GracefulRestart",
+            "ForceRestart",
+            "ForceOn",
+            "PushPowerButton",
+            "PowerCycle",
+        ],
+    }],
+};
+
+/// The power state a system in `power_state` ends up in after a reset of
+/// `reset_type`, or the message explaining why the reset can't be applied.
+pub fn reset(power_state: &PowerState, reset_type: ResetType) -> Result<PowerState, Base> {
+    use ResetType::*;
+    match reset_type {
+        GracefulRestart | ForceRestart | On | ForceOn | PowerCycle => Ok(PowerState::On),
+        ForceOff | GracefulShutdown => Ok(PowerState::Off),
+        Nmi | Suspend | Pause | Resume => Err(Base::PropertyNotUpdated("PowerState".to_string())),
+        PushPowerButton => match power_state {
+            PowerState::On | PowerState::PoweringOn => Ok(PowerState::Off),
+            PowerState::Off | PowerState::PoweringOff => Ok(PowerState::On),
+            PowerState::Paused => Err(Base::PropertyValueError("PowerState".to_string())),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::{prelude::*, sample::select};
+
+    const POWER_STATES: [PowerState; 5] = [
+        PowerState::On,
+        PowerState::Off,
+        PowerState::PoweringOn,
+        PowerState::PoweringOff,
+        PowerState::Paused,
+    ];
+
+    const RESET_TYPES: [ResetType; 12] = [
+        ResetType::On,
+        ResetType::ForceOff,
+        ResetType::GracefulShutdown,
+        ResetType::GracefulRestart,
+        ResetType::ForceRestart,
+        ResetType::Nmi,
+        ResetType::ForceOn,
+        ResetType::PushPowerButton,
+        ResetType::PowerCycle,
+        ResetType::Suspend,
+        ResetType::Pause,
+        ResetType::Resume,
+    ];
+
+    fn power_state() -> impl Strategy<Value = PowerState> {
+        select(POWER_STATES.to_vec())
+    }
+
+    fn reset_type() -> impl Strategy<Value = ResetType> {
+        select(RESET_TYPES.to_vec())
+    }
+
+    /// The state a system settles in, once any transition has finished.
+    fn settled(power_state: &PowerState) -> Option<PowerState> {
+        match power_state {
+            PowerState::On | PowerState::PoweringOn => Some(PowerState::On),
+            PowerState::Off | PowerState::PoweringOff => Some(PowerState::Off),
+            PowerState::Paused => None,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn reset_settles_on_or_off(power_state in power_state(), reset_type in reset_type()) {
+            if let Ok(next) = reset(&power_state, reset_type) {
+                prop_assert!(matches!(next, PowerState::On | PowerState::Off));
+            }
+        }
+
+        #[test]
+        fn on_resets_power_on(
+            power_state in power_state(),
+            reset_type in select(vec![
+                ResetType::On,
+                ResetType::ForceOn,
+                ResetType::GracefulRestart,
+                ResetType::ForceRestart,
+                ResetType::PowerCycle,
+            ]),
+        ) {
+            prop_assert_eq!(reset(&power_state, reset_type).ok(), Some(PowerState::On));
+        }
+
+        #[test]
+        fn off_resets_power_off(
+            power_state in power_state(),
+            reset_type in select(vec![ResetType::ForceOff, ResetType::GracefulShutdown]),
+        ) {
+            prop_assert_eq!(reset(&power_state, reset_type).ok(), Some(PowerState::Off));
+        }
+
+        #[test]
+        fn push_power_button_toggles(power_state in power_state()) {
+            let pushed = reset(&power_state, ResetType::PushPowerButton).ok();
+            let expected = match settled(&power_state) {
+                Some(PowerState::On) => Some(PowerState::Off),
+                Some(_) => Some(PowerState::On),
+                None => None,
+            };
+            prop_assert_eq!(pushed, expected);
+        }
+
+        #[test]
+        fn push_power_button_twice_restores(power_state in power_state()) {
+            let twice = reset(&power_state, ResetType::PushPowerButton)
+                .and_then(|pushed| reset(&pushed, ResetType::PushPowerButton))
+                .ok();
+            prop_assert_eq!(twice, settled(&power_state));
+        }
+
+        #[test]
+        fn unsupported_resets_fail(
+            power_state in power_state(),
+            reset_type in select(vec![
+                ResetType::Nmi,
+                ResetType::Suspend,
+                ResetType::Pause,
+                ResetType::Resume,
+            ]),
+        ) {
+            prop_assert!(reset(&power_state, reset_type).is_err());
+        }
+
+        #[test]
+        fn allowable_reset_types_apply(
+            power_state in select(vec![PowerState::On, PowerState::Off]),
+            reset_type in reset_type(),
+        ) {
+            let name = serde_json::to_value(reset_type).unwrap();
+            let allowed = RESET_ACTION_INFO.parameters[0]
+                .allowable_values
+                .iter()
+                .any(|value| name == *value);
+            prop_assert_eq!(reset(&power_state, reset_type).is_ok(), allowed);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, Request, StatusCode},
+    routing::get,
+};
+use tower::ServiceExt;
+
+use crate::openapi::Routes;
+
+/// Renders the resources of `routes` once, by requesting them, and returns
+/// routes that serve the rendered responses without running any handler or
+/// serializer per request. Only suitable for resources that do not change
+/// while the service is running and that need no authentication.
+pub async fn prerender(routes: Routes) -> anyhow::Result<Routes> {
+    let (app, described) = routes.into_parts();
+    let mut prerendered = Routes::default();
+    for (path, route) in described {
+        let request = Request::get(&path).body(Body::empty())?;
+        let response = app.clone().oneshot(request).await?;
+        if !response.status().is_success() {
+            anyhow::bail!("failed to pre-render {}: {}", path, response.status());
+        }
+
+        let (parts, body) = response.into_parts();
+        let rendered: (StatusCode, HeaderMap, Bytes) = (
+            parts.status,
+            parts.headers,
+            hyper::body::to_bytes(body).await?,
+        );
+        prerendered = prerendered.route(
+            &path,
+            get(move || std::future::ready(rendered.clone())),
+            route,
+        );
+    }
+    Ok(prerendered)
+}
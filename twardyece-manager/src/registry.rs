@@ -0,0 +1,277 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, Request, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use redfish_codegen::registries::base::v1_15_0::Base;
+use seuss::{
+    auth::{BasicAuthentication, Role},
+    redfish_error,
+};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+use tower::ServiceExt;
+
+use crate::{
+    action, auth,
+    links::{self, LinkBuilder},
+    openapi::{self, Routes},
+};
+
+/// Registration of resource subtrees while the service is running, e.g. for
+/// BMCs discovered by aggregation.
+pub trait ResourceRegistry {
+    /// Serves `subtree` for `prefix` and every path below it, replacing any
+    /// subtree already registered at `prefix`. Prefixes, and the routes in
+    /// `subtree`, are matched against the request path below the base path.
+    fn register(&self, prefix: String, subtree: Router);
+
+    /// Removes the subtrees at and below `prefix`, returning how many were
+    /// removed.
+    fn unregister(&self, prefix: &str) -> usize;
+}
+
+/// Subtrees registered at runtime. These are served by the fallback of the
+/// main router, so they can never shadow the routes built in main.
+#[derive(Clone, Default)]
+pub struct Registry {
+    subtrees: Arc<Mutex<BTreeMap<String, Router>>>,
+    mounted: Arc<Mutex<Vec<String>>>,
+}
+
+fn is_below(path: &str, prefix: &str) -> bool {
+    path == prefix || (path.starts_with(prefix) && path[prefix.len()..].starts_with('/'))
+}
+
+/// Whether `route`, as given to [Router::route], matches `path`.
+fn matches(route: &str, path: &str) -> bool {
+    let mut segments = path.split('/');
+    for pattern in route.split('/') {
+        if pattern.starts_with('*') {
+            return true;
+        }
+        match segments.next() {
+            Some(segment) if pattern.starts_with(':') && !segment.is_empty() => {}
+            Some(segment) if segment == pattern => {}
+            _ => return false,
+        }
+    }
+    segments.next().is_none()
+}
+
+impl ResourceRegistry for Registry {
+    fn register(&self, prefix: String, subtree: Router) {
+        tracing::info!("registered resources at {}", prefix);
+        self.subtrees.lock().unwrap().insert(prefix, subtree);
+    }
+
+    fn unregister(&self, prefix: &str) -> usize {
+        let mut subtrees = self.subtrees.lock().unwrap();
+        let before = subtrees.len();
+        subtrees.retain(|path, _| !is_below(path, prefix));
+        let removed = before - subtrees.len();
+        if removed > 0 {
+            tracing::info!("unregistered {} resources at {}", removed, prefix);
+        }
+        removed
+    }
+}
+
+impl Registry {
+    /// The prefixes of the registered subtrees, below the base path.
+    pub fn prefixes(&self) -> Vec<String> {
+        self.subtrees.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Records the routes of the main router. The fallback never sees
+    /// requests for these, so nothing may be registered over them.
+    pub fn reserve<'a>(&self, routes: impl IntoIterator<Item = &'a str>) {
+        let mut mounted = self.mounted.lock().unwrap();
+        mounted.extend(routes.into_iter().map(|route| route.to_string()));
+    }
+
+    fn is_mounted(&self, path: &str) -> bool {
+        let mounted = self.mounted.lock().unwrap();
+        mounted.iter().any(|route| matches(route, path))
+    }
+
+    /// Routes `request` to the subtree with the longest matching prefix.
+    pub async fn dispatch(self, request: Request<Body>) -> Response {
+        let subtree = {
+            let subtrees = self.subtrees.lock().unwrap();
+            subtrees
+                .iter()
+                .filter(|(prefix, _)| is_below(request.uri().path(), prefix))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map(|(_, subtree)| subtree.clone())
+        };
+        match subtree {
+            Some(subtree) => subtree.oneshot(request).await.into_response(),
+            None => StatusCode::NOT_FOUND.into_response(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RegisterRequest {
+    /// Resources to serve, each at its own @odata.id.
+    resources: Vec<serde_json::Value>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct UnregisterRequest {
+    prefix: String,
+}
+
+fn error(status: StatusCode, message: Base) -> Response {
+    (status, Json(redfish_error::one_message(message.into()))).into_response()
+}
+
+/// This surface is not served by seuss, so it accepts Basic authentication
+/// only. Returns the response to send instead if the client may not proceed.
+fn authorize<A: BasicAuthentication>(
+    authenticator: &A,
+    headers: &HeaderMap,
+    administrator: bool,
+) -> Option<Response> {
+    let (username, password) = match auth::basic_credentials(headers) {
+        Some(credentials) => credentials,
+        None => return Some(error(StatusCode::UNAUTHORIZED, Base::NoValidSession)),
+    };
+    match authenticator.authenticate(username, password) {
+        Ok(user) if administrator && user.role != Role::Administrator => {
+            Some(error(StatusCode::FORBIDDEN, Base::InsufficientPrivilege))
+        }
+        Ok(_) => None,
+        Err(error) => Some((StatusCode::UNAUTHORIZED, Json(error)).into_response()),
+    }
+}
+
+/// OEM surface for registering static JSON resources at runtime, mounted at
+/// `path`. Registered resources must live below the service root, and may
+/// not collide with the routes reserved in `registry`. Only administrators
+/// may register resources, and any authenticated user may read them.
+pub fn routes<A>(registry: Registry, authenticator: A, path: &str, link: LinkBuilder) -> Routes
+where
+    A: BasicAuthentication + Clone + Send + 'static,
+{
+    let root = link.id(links::SERVICE_ROOT).0;
+    let (list, list_link, reader) = (registry.clone(), link.clone(), authenticator.clone());
+    let register = {
+        let (registry, authenticator, link) =
+            (registry.clone(), authenticator.clone(), link.clone());
+        move |headers: HeaderMap, Json(body): Json<RegisterRequest>| async move {
+            if let Some(response) = authorize(&authenticator, &headers, true) {
+                return response;
+            }
+
+            let mut resources = Vec::new();
+            for resource in body.resources {
+                let id = resource.get("@odata.id").and_then(|id| id.as_str());
+                match id {
+                    // Requests reach the registry with the base path stripped.
+                    Some(id) if is_below(id, root.trim_end_matches('/')) => {
+                        let path = &id[link.base_path().len()..];
+                        if registry.is_mounted(path) {
+                            let message = Base::ResourceAlreadyExists(
+                                "Resource".to_string(),
+                                "@odata.id".to_string(),
+                                id.to_string(),
+                            );
+                            return error(StatusCode::CONFLICT, message);
+                        }
+                        resources.push((path.to_string(), resource.clone()))
+                    }
+                    Some(id) => {
+                        let message =
+                            Base::PropertyValueOutOfRange(id.to_string(), "@odata.id".to_string());
+                        return error(StatusCode::BAD_REQUEST, message);
+                    }
+                    None => {
+                        let message = Base::PropertyMissing("@odata.id".to_string());
+                        return error(StatusCode::BAD_REQUEST, message);
+                    }
+                }
+            }
+            for (id, resource) in resources {
+                let authenticator = authenticator.clone();
+                let read = move |headers: HeaderMap| async move {
+                    match authorize(&authenticator, &headers, false) {
+                        Some(response) => response,
+                        None => Json(resource).into_response(),
+                    }
+                };
+                let subtree = Router::new().route(&id, get(read));
+                registry.register(id, subtree);
+            }
+            Json(redfish_error::one_message(Base::Success.into())).into_response()
+        }
+    };
+    let unregister = move |headers: HeaderMap, Json(body): Json<UnregisterRequest>| async move {
+        if let Some(response) = authorize(&authenticator, &headers, true) {
+            return response;
+        }
+        let removed = match body.prefix.strip_prefix(link.base_path()) {
+            Some(prefix) if prefix.starts_with('/') => registry.unregister(prefix),
+            _ => 0,
+        };
+        match removed {
+            0 => error(
+                StatusCode::NOT_FOUND,
+                Base::ResourceNotFound("Resource".to_string(), body.prefix),
+            ),
+            _ => Json(redfish_error::one_message(Base::Success.into())).into_response(),
+        }
+    };
+
+    Routes::default()
+        .route(
+            path,
+            get(move |headers: HeaderMap| async move {
+                match authorize(&reader, &headers, false) {
+                    Some(response) => response,
+                    None => {
+                        let prefixes: Vec<String> = list
+                            .prefixes()
+                            .iter()
+                            .map(|prefix| list_link.id(prefix).0)
+                            .collect();
+                        Json(serde_json::json!({ "Prefixes": prefixes })).into_response()
+                    }
+                }
+            }),
+            openapi::GET,
+        )
+        .route(
+            &links::action(path, "Register"),
+            action::target(post(register)),
+            openapi::POST,
+        )
+        .route(
+            &links::action(path, "Unregister"),
+            action::target(post(unregister)),
+            openapi::POST,
+        )
+}
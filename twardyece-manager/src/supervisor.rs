@@ -0,0 +1,127 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use std::{fmt, future::Future, time::Duration};
+use tokio::task::JoinSet;
+
+/// What to do when a subsystem fails, by returning an error or panicking:
+/// restart it up to `max` times, waiting `backoff` times the number of
+/// restarts so far before each attempt.
+#[derive(Clone, Copy)]
+pub struct Restart {
+    pub max: u32,
+    pub backoff: Duration,
+}
+
+pub enum Health {
+    Running,
+    Restarting(u32),
+    Stopped,
+    Failed(String),
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Health::Running => write!(f, "running"),
+            Health::Restarting(restarts) => write!(f, "restarting (attempt {})", restarts),
+            Health::Stopped => write!(f, "stopped"),
+            Health::Failed(error) => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+type Outcome = (&'static str, bool, anyhow::Result<()>);
+
+/// Runs the subsystems of the service as separate tasks, restarting them
+/// according to their policy, and logs every change in their health. A
+/// subsystem that fails for good only brings the service down if it is
+/// critical.
+#[derive(Default)]
+pub struct Supervisor {
+    tasks: JoinSet<Outcome>,
+}
+
+fn report(name: &'static str, state: Health) {
+    tracing::info!(subsystem = name, health = %state);
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the future returned by `start` as the subsystem `name`. `start`
+    /// is called again for every restart.
+    pub fn spawn<F, Fut>(&mut self, name: &'static str, restart: Restart, critical: bool, start: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        report(name, Health::Running);
+        self.tasks.spawn(async move {
+            let mut restarts = 0;
+            loop {
+                // Each attempt runs in its own task, so a panic is reported
+                // as a failure instead of tearing down the supervisor.
+                let result = match tokio::spawn(start()).await {
+                    Ok(result) => result,
+                    Err(error) => Err(anyhow::anyhow!("panicked: {}", error)),
+                };
+                let error = match result {
+                    Ok(()) => {
+                        report(name, Health::Stopped);
+                        return (name, critical, Ok(()));
+                    }
+                    Err(error) => error,
+                };
+
+                if restarts >= restart.max {
+                    tracing::error!(subsystem = name, "failed: {:#}", error);
+                    report(name, Health::Failed(format!("{:#}", error)));
+                    return (name, critical, Err(error));
+                }
+                restarts += 1;
+                tracing::warn!(
+                    subsystem = name,
+                    restarts,
+                    "restarting after failure: {:#}",
+                    error
+                );
+                report(name, Health::Restarting(restarts));
+                tokio::time::sleep(restart.backoff * restarts).await;
+                report(name, Health::Running);
+            }
+        });
+    }
+
+    /// Waits until a critical subsystem stops, or until every subsystem has
+    /// stopped, and returns the result of the critical subsystem.
+    pub async fn run(mut self) -> anyhow::Result<()> {
+        let mut result = Ok(());
+        while let Some(outcome) = self.tasks.join_next().await {
+            let (name, critical, outcome) = outcome?;
+            if critical {
+                result = outcome.with_context(|| format!("subsystem {} failed", name));
+                break;
+            }
+        }
+
+        self.tasks.shutdown().await;
+        result
+    }
+}
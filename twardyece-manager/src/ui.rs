@@ -0,0 +1,67 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+};
+use include_dir::{include_dir, Dir};
+
+use crate::openapi::{self, Routes};
+
+static ASSETS: Dir = include_dir!("$CARGO_MANIFEST_DIR/ui");
+
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("json") => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+async fn asset(Path(path): Path<String>) -> Response {
+    let path = if path.is_empty() { "index.html" } else { &path };
+    match ASSETS.get_file(path) {
+        Some(file) => (
+            [(header::CONTENT_TYPE, content_type(path))],
+            file.contents(),
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// A read-only dashboard at `/ui/`, which reads everything it shows through
+/// the Redfish API of the service, with a session of the user's own.
+pub fn routes() -> Routes {
+    Routes::default()
+        .route(
+            "/ui",
+            get(|| async { Redirect::permanent("ui/") }),
+            openapi::GET.public(),
+        )
+        .route(
+            "/ui/",
+            get(|| asset(Path(String::new()))),
+            openapi::GET.public(),
+        )
+        .route("/ui/*path", get(asset), openapi::GET.public())
+}
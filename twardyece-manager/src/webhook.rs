@@ -0,0 +1,116 @@
+// Author: Ethan D. Twardy <ethan.twardy@gmail.com>
+//
+// Copyright 2023, Ethan Twardy. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the \"License\");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an \"AS IS\" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    events::{Event, Events},
+    supervisor::{Restart, Supervisor},
+};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::broadcast::error::RecvError;
+
+/// An HTTP endpoint that is sent a POST for selected events, e.g. for chat-ops
+/// or home automation.
+#[derive(Clone, serde::Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// Kinds of events that trigger the webhook, e.g. power-state-changed.
+    /// Every event triggers it when this is empty.
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// JSON body of the request. Occurrences of `{name}` in its strings are
+    /// replaced by the property `name` of the event, e.g. `{system}`. When
+    /// absent, the body is an object holding every property of the event.
+    pub payload: Option<serde_json::Value>,
+}
+
+fn render(template: &serde_json::Value, event: &Event) -> serde_json::Value {
+    use serde_json::Value;
+    match template {
+        Value::String(template) => Value::String(event.format(template)),
+        Value::Array(values) => {
+            Value::Array(values.iter().map(|value| render(value, event)).collect())
+        }
+        Value::Object(values) => Value::Object(
+            values
+                .iter()
+                .map(|(key, value)| (key.clone(), render(value, event)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+impl Webhook {
+    fn wants(&self, event: &Event) -> bool {
+        self.events.is_empty() || self.events.iter().any(|kind| kind == event.kind())
+    }
+
+    fn payload(&self, event: &Event) -> serde_json::Value {
+        match &self.payload {
+            Some(template) => render(template, event),
+            None => event.to_json(),
+        }
+    }
+}
+
+async fn deliver(webhooks: Arc<Vec<Webhook>>, events: Events) -> anyhow::Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let mut events = events.subscribe();
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                tracing::warn!("webhooks missed {} events", missed);
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+        for webhook in webhooks.iter().filter(|webhook| webhook.wants(&event)) {
+            // Each delivery runs on its own, so a slow endpoint does not hold
+            // up the others.
+            let request = client.post(&webhook.url).json(&webhook.payload(&event));
+            let url = webhook.url.clone();
+            tokio::spawn(async move {
+                let response = request
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                if let Err(error) = response {
+                    tracing::warn!("webhook {} failed: {}", url, error);
+                }
+            });
+        }
+    }
+}
+
+/// Delivers the events published to `events` to the configured webhooks.
+pub fn start(supervisor: &mut Supervisor, webhooks: Vec<Webhook>, events: Events) {
+    if webhooks.is_empty() {
+        return;
+    }
+    let webhooks = Arc::new(webhooks);
+    supervisor.spawn(
+        "webhooks",
+        Restart {
+            max: 3,
+            backoff: Duration::from_secs(1),
+        },
+        false,
+        move || deliver(webhooks.clone(), events.clone()),
+    );
+}
@@ -0,0 +1,106 @@
+// Read-only dashboard built on the service's own Redfish API. The page is
+// served at <base>/ui/, so the API is found relative to it.
+const api = new URL("../redfish/v1/", document.baseURI);
+
+const login = document.getElementById("login");
+const logout = document.getElementById("logout");
+const systems = document.getElementById("systems");
+const error = document.getElementById("error");
+
+function session() {
+  return JSON.parse(sessionStorage.getItem("session"));
+}
+
+async function get(path) {
+  const response = await fetch(new URL(path, api), {
+    headers: { "X-Auth-Token": session().token },
+  });
+  if (response.status === 401) {
+    sessionStorage.removeItem("session");
+    show();
+    throw new Error("The session has expired.");
+  }
+  if (!response.ok) {
+    throw new Error(`GET ${path} failed: ${response.status}`);
+  }
+  return response.json();
+}
+
+function cell(row, text, className) {
+  const td = row.insertCell();
+  td.textContent = text ?? "";
+  if (className) {
+    td.className = className;
+  }
+}
+
+async function refresh() {
+  const collection = await get("Systems");
+  const members = await Promise.all(
+    collection.Members.map((member) => get(member["@odata.id"]))
+  );
+  const body = systems.querySelector("tbody");
+  body.replaceChildren();
+  for (const system of members) {
+    const row = body.insertRow();
+    cell(row, system.Id);
+    cell(row, system.Name);
+    cell(row, system.PowerState, system.PowerState);
+    cell(row, system.Status?.State);
+    cell(row, system.Manufacturer);
+    cell(row, system.Model);
+    cell(row, system.SerialNumber);
+  }
+}
+
+function show() {
+  const loggedIn = session() !== null;
+  login.hidden = loggedIn;
+  logout.hidden = !loggedIn;
+  systems.hidden = !loggedIn;
+  if (loggedIn) {
+    refresh().catch((e) => (error.textContent = e.message));
+  }
+}
+
+login.addEventListener("submit", async (event) => {
+  event.preventDefault();
+  error.textContent = "";
+  const form = new FormData(login);
+  const response = await fetch(new URL("SessionService/Sessions", api), {
+    method: "POST",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify({
+      UserName: form.get("username"),
+      Password: form.get("password"),
+    }),
+  });
+  if (!response.ok) {
+    error.textContent = "Login failed.";
+    return;
+  }
+  sessionStorage.setItem(
+    "session",
+    JSON.stringify({
+      token: response.headers.get("X-Auth-Token"),
+      location: response.headers.get("Location"),
+    })
+  );
+  login.reset();
+  show();
+});
+
+logout.addEventListener("click", async () => {
+  const { token, location } = session();
+  sessionStorage.removeItem("session");
+  show();
+  if (location) {
+    await fetch(new URL(location, api), {
+      method: "DELETE",
+      headers: { "X-Auth-Token": token },
+    });
+  }
+});
+
+show();
+setInterval(() => session() && refresh().catch((e) => (error.textContent = e.message)), 10000);